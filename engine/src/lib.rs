@@ -76,6 +76,56 @@ pub async fn run(reloader: Receiver<()>) -> Result<(), String> {
                     let color = Color::from_rgba(color.r, color.g, color.b, 255);
                     draw_rectangle(x, y, width, height, color)
                 }
+                RenderCommand::Line {
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    thickness,
+                    color,
+                } => {
+                    let color = Color::from_rgba(color.r, color.g, color.b, 255);
+                    draw_line(x1, y1, x2, y2, thickness, color)
+                }
+                RenderCommand::Arc {
+                    x,
+                    y,
+                    radius,
+                    start,
+                    end,
+                    thickness,
+                    color,
+                } => {
+                    let color = Color::from_rgba(color.r, color.g, color.b, 255);
+                    // one segment every few pixels is enough to look round
+                    let segments = ((end - start) * radius / 4.0).ceil().max(1.0) as usize;
+                    let step = (end - start) / segments as f32;
+                    for i in 0..segments {
+                        let a = start + step * i as f32;
+                        let b = a + step;
+                        draw_line(
+                            x + radius * a.cos(),
+                            y + radius * a.sin(),
+                            x + radius * b.cos(),
+                            y + radius * b.sin(),
+                            thickness,
+                            color,
+                        );
+                    }
+                }
+                RenderCommand::FillPolygon { points, color } => {
+                    let color = Color::from_rgba(color.r, color.g, color.b, 255);
+                    // triangle fan around the centroid
+                    let n = points.len() as f32;
+                    let center = points
+                        .iter()
+                        .fold(vec2(0.0, 0.0), |acc, (x, y)| acc + vec2(*x, *y))
+                        / n;
+                    for (i, (x, y)) in points.iter().enumerate() {
+                        let (next_x, next_y) = points[(i + 1) % points.len()];
+                        draw_triangle(center, vec2(*x, *y), vec2(next_x, next_y), color);
+                    }
+                }
                 RenderCommand::Text { x, y, text } => {
                     let params = TextParams {
                         font,
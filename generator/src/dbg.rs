@@ -1,8 +1,16 @@
+use crate::dijkstra::Solution;
 use crate::generator::*;
-use crate::{GameState, Overlay};
+use crate::graph::{Graph, GraphFormat};
+use crate::grid::Grid;
+use crate::maze::{dedup, JsonFormat, Mask, Maze, MazeDocument, Room, Topology, Transform, Wrap};
+use crate::raster::Raster;
+use crate::save::SavedMaze;
+use crate::svg::{Svg, SvgStyle};
+use crate::{GameState, Overlay, SaveFormat};
 use egui::{Button, CtxRef, Slider};
-use host_api::{HostApi, Input};
-use std::cmp;
+use host_api::{Color, HostApi, Input};
+use rand::{prelude::StdRng, Rng, SeedableRng};
+use std::{cmp, fs, path::Path};
 
 #[no_mangle]
 pub extern "C" fn dbg_update(
@@ -15,6 +23,14 @@ pub extern "C" fn dbg_update(
     let steps = wilson.steps_count() - 1;
     let completed = wilson.completed();
     let next_step = wilson.next_step();
+    let shown = state.braided.as_ref().unwrap_or_else(|| wilson.maze());
+    let levels = shown.levels();
+    let has_ascii = shown.has_ascii();
+    let loaded = state.loaded.is_some();
+    let mut print_requested = false;
+    let mut save_requested = false;
+    let mut load_requested = false;
+    let mut count_requested = false;
 
     egui::Window::new("debug").show(egui_ctx, |ui| {
         ui.horizontal(|ui| {
@@ -35,7 +51,49 @@ pub extern "C" fn dbg_update(
                         Generator::RecurBacktracker,
                         "Recur. Backtracker",
                     );
+                    ui.selectable_value(
+                        &mut state.generator,
+                        Generator::AldousBroder,
+                        "Aldous-Broder",
+                    );
+                    ui.selectable_value(&mut state.generator, Generator::Kruskal, "Kruskal");
+                    ui.selectable_value(&mut state.generator, Generator::Prim, "Prim");
+                    ui.selectable_value(&mut state.generator, Generator::TruePrim, "True Prim");
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Grid:");
+            egui::ComboBox::from_id_source("topology")
+                .selected_text(format!("{:?}", state.debug.debug_topology))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(
+                        &mut state.debug.debug_topology,
+                        Topology::Square,
+                        "Square",
+                    );
+                    ui.selectable_value(&mut state.debug.debug_topology, Topology::Hex, "Hex");
+                    ui.selectable_value(&mut state.debug.debug_topology, Topology::Polar, "Polar");
+                    ui.selectable_value(
+                        &mut state.debug.debug_topology,
+                        Topology::Triangle,
+                        "Triangle",
+                    );
+                    ui.selectable_value(&mut state.debug.debug_topology, Topology::Weave, "Weave");
                 });
+            if state.debug.debug_topology == Topology::Square {
+                ui.label("Wrap:");
+                egui::ComboBox::from_id_source("wrap")
+                    .selected_text(format!("{:?}", state.debug.debug_wrap))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut state.debug.debug_wrap, Wrap::None, "None");
+                        ui.selectable_value(
+                            &mut state.debug.debug_wrap,
+                            Wrap::Cylinder,
+                            "Cylinder",
+                        );
+                        ui.selectable_value(&mut state.debug.debug_wrap, Wrap::Torus, "Torus");
+                    });
+            }
         });
         ui.horizontal(|ui| {
             ui.label("Width:");
@@ -50,7 +108,43 @@ pub extern "C" fn dbg_update(
                     .clamp_to_range(true)
                     .integer(),
             );
+            ui.label("Levels: ");
+            ui.add(
+                Slider::new(&mut state.debug.debug_maze_levels, 1..=10)
+                    .clamp_to_range(true)
+                    .integer(),
+            );
         });
+        if levels > 1 {
+            ui.horizontal(|ui| {
+                ui.label("Level:");
+                ui.add(
+                    Slider::new(&mut state.debug.debug_level, 0..=levels - 1)
+                        .clamp_to_range(true)
+                        .integer(),
+                );
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.label("Mask:");
+            ui.text_edit_singleline(&mut state.debug.debug_mask_path);
+        });
+        if state.debug.debug_topology != Topology::Polar {
+            ui.horizontal(|ui| {
+                ui.label("Rooms:");
+                ui.add(
+                    Slider::new(&mut state.debug.debug_rooms, 0..=20)
+                        .clamp_to_range(true)
+                        .integer(),
+                );
+                ui.label("Doors:");
+                ui.add(
+                    Slider::new(&mut state.debug.debug_doors, 1..=4)
+                        .clamp_to_range(true)
+                        .integer(),
+                );
+            });
+        }
         ui.horizontal(|ui| {
             ui.label("Steps:");
             let slider = Slider::new(&mut state.debug.debug_step, 0..=steps).clamp_to_range(true);
@@ -93,9 +187,30 @@ pub extern "C" fn dbg_update(
                 }
             }
         });
+        if finished || loaded {
+            ui.horizontal(|ui| {
+                ui.label("Braid %:");
+                ui.add(
+                    Slider::new(&mut state.debug.debug_braid, 0..=100)
+                        .clamp_to_range(true)
+                        .integer(),
+                );
+                ui.label("Transform:");
+                let transform = &mut state.debug.debug_transform;
+                let selected = transform.map_or("None".to_string(), |t| format!("{:?}", t));
+                egui::ComboBox::from_id_source("transform")
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(transform, None, "None");
+                        for t in Transform::ALL.iter().cloned() {
+                            ui.selectable_value(transform, Some(t), format!("{:?}", t));
+                        }
+                    });
+            });
+        }
         ui.horizontal(|ui| {
             ui.color_edit_button_srgb(&mut state.debug.debug_borders_color);
-            if completed {
+            if completed || loaded {
                 ui.radio_value(&mut state.overlay, None, "none");
                 ui.radio_value(&mut state.overlay, Some(Overlay::Distances), "distances");
                 ui.radio_value(
@@ -133,6 +248,9 @@ pub extern "C" fn dbg_update(
         //     }
         // });
         // ui.separator();
+        if cfg!(debug_assertions) {
+            ui.checkbox(&mut state.debug.debug_validate, "validate steps");
+        }
         ui.horizontal(|ui| {
             if ui.button("restart").clicked() {
                 state.debug.reload_requested = true;
@@ -141,8 +259,89 @@ pub extern "C" fn dbg_update(
                 state.debug.reload_requested = true;
                 state.debug.finish_requested = true;
             }
+            if has_ascii && ui.button("print").clicked() {
+                print_requested = true;
+            }
+            if ui.button("count layouts").clicked() {
+                count_requested = true;
+            }
+        });
+        if completed || loaded {
+            ui.horizontal(|ui| {
+                ui.label("Save:");
+                ui.text_edit_singleline(&mut state.debug.debug_save_path);
+                let format = &mut state.debug.debug_save_format;
+                ui.radio_value(format, SaveFormat::Binary, "binary");
+                ui.radio_value(format, SaveFormat::Json(JsonFormat::Bitmasks), "json");
+                ui.radio_value(format, SaveFormat::Json(JsonFormat::Walls), "json walls");
+                ui.radio_value(format, SaveFormat::Svg, "svg");
+                ui.radio_value(format, SaveFormat::Png, "png");
+                ui.radio_value(format, SaveFormat::Graph(GraphFormat::Dot), "dot");
+                ui.radio_value(format, SaveFormat::Graph(GraphFormat::GraphMl), "graphml");
+                if *format == SaveFormat::Png {
+                    ui.add(
+                        Slider::new(&mut state.debug.debug_png_cell_size, 4.0..=80.0)
+                            .clamp_to_range(true)
+                            .text("cell size"),
+                    );
+                }
+                let path = !state.debug.debug_save_path.is_empty();
+                if ui.add(Button::new("save").enabled(path)).clicked() {
+                    save_requested = true;
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.label("Load:");
+            ui.text_edit_singleline(&mut state.debug.debug_load_path);
+            let path = !state.debug.debug_load_path.is_empty();
+            if ui.add(Button::new("load").enabled(path)).clicked() {
+                load_requested = true;
+            }
         });
     });
+    if print_requested {
+        let maze = state
+            .braided
+            .as_ref()
+            .unwrap_or_else(|| state.wilson.maze().maze());
+        println!("{}", maze.to_ascii());
+    }
+    if save_requested {
+        debug_save_maze(state);
+    }
+    if load_requested {
+        debug_load_maze(state);
+    }
+    if count_requested {
+        debug_count_layouts(state);
+    }
+    if let Some(stats) = &state.stats {
+        egui::Window::new("stats").show(egui_ctx, |ui| {
+            ui.label(format!("cells: {}", stats.cells));
+            ui.label(format!(
+                "dead ends: {} ({:.1}%)",
+                stats.dead_ends(),
+                stats.dead_end_ratio() * 100.0
+            ));
+            for (degree, count) in stats.junctions() {
+                ui.label(format!("{}-way junctions: {}", degree, count));
+            }
+            ui.label(format!(
+                "river factor: {:.1}%",
+                stats.river_factor() * 100.0
+            ));
+            ui.label(format!(
+                "average corridor length: {:.2}",
+                stats.average_corridor_length()
+            ));
+            ui.label(format!("solution length: {}", stats.solution_length));
+            ui.label(format!("tortuosity: {:.2}", stats.tortuosity));
+            if let Some(hash) = stats.hash {
+                ui.label(format!("hash: {:016x}", hash));
+            }
+        });
+    }
     true
 }
 
@@ -150,40 +349,32 @@ pub fn debug_reload_maze(state: &mut GameState, _input: &Input) {
     if state.debug.reload_requested {
         state.distances.clear();
         state.longest_path.clear();
+        state.stats = None;
         state.overlay = None;
         state.maze_width = state.debug.debug_maze_width;
         state.maze_height = state.debug.debug_maze_height;
         state.debug.reload_requested = false;
-        let new_wilson = match state.generator {
-            Generator::BinaryTree => MazeGen::BinaryTree(Box::new(BinaryTreeGen::new(
-                state.maze_width,
-                state.maze_height,
-            ))),
-            Generator::Sidewind => MazeGen::Sidewind(Box::new(SidewinderGen::new(
-                state.maze_width,
-                state.maze_height,
-            ))),
-            Generator::Wilson => MazeGen::Wilson(Box::new(WilsonGen::new(
-                &mut state.rng,
-                state.maze_width,
-                state.maze_height,
-            ))),
-            Generator::HuntAndKill => MazeGen::HuntAndKill(Box::new(HuntAndKillGen::new(
-                state.maze_width,
-                state.maze_height,
-            ))),
-            Generator::RecurBacktracker => MazeGen::RecurBacktracker(Box::new(
-                RecurBacktrackerGen::new(state.maze_width, state.maze_height),
-            )),
-        };
+        state.loaded = None;
+        state.debug.debug_level = 0;
+        state.debug.debug_braid = 0;
+        state.debug.debug_transform = None;
+        state.seed = state.rng.gen();
+        state.rng = StdRng::seed_from_u64(state.seed);
+        let maze = debug_new_maze(state);
+        let new_wilson = debug_generator(state.generator, &mut state.rng, maze);
         state.debug.debug_step =
             cmp::min(state.debug.debug_step, new_wilson.maze().steps_count() - 1);
         state.wilson = new_wilson;
     }
 
+    let validate = cfg!(debug_assertions) && state.debug.debug_validate;
     let wilson = state.wilson.maze_mut();
     if state.debug.finish_requested {
-        wilson.finish(&mut state.rng);
+        if validate {
+            debug_validated_goto_step(wilson, &mut state.rng, usize::MAX);
+        } else {
+            wilson.finish(&mut state.rng);
+        }
         state.debug.debug_step = wilson.steps_count() - 1;
         state.debug.finish_requested = false;
     }
@@ -191,7 +382,313 @@ pub fn debug_reload_maze(state: &mut GameState, _input: &Input) {
         state.debug.debug_step = (state.debug.debug_step % wilson.steps_count()) + 1;
     }
     if state.debug.debug_step != wilson.next_step() {
-        wilson.goto_step(&mut state.rng, state.debug.debug_step);
+        if validate {
+            debug_validated_goto_step(wilson, &mut state.rng, state.debug.debug_step);
+        } else {
+            wilson.goto_step(&mut state.rng, state.debug.debug_step);
+        }
         state.debug.debug_step = wilson.next_step();
     }
+    debug_braid_maze(state);
+}
+
+fn debug_generator(generator: Generator, rng: &mut StdRng, maze: Maze) -> MazeGen {
+    match generator {
+        Generator::BinaryTree => MazeGen::BinaryTree(Box::new(BinaryTreeGen::new(maze))),
+        Generator::Sidewind => MazeGen::Sidewind(Box::new(SidewinderGen::new(maze))),
+        Generator::Wilson => MazeGen::Wilson(Box::new(WilsonGen::new(rng, maze))),
+        Generator::HuntAndKill => MazeGen::HuntAndKill(Box::new(HuntAndKillGen::new(maze))),
+        Generator::RecurBacktracker => {
+            MazeGen::RecurBacktracker(Box::new(RecurBacktrackerGen::new(maze)))
+        }
+        Generator::AldousBroder => MazeGen::AldousBroder(Box::new(AldousBroderGen::new(maze))),
+        Generator::Kruskal => MazeGen::Kruskal(Box::new(KruskalGen::new(rng, maze))),
+        Generator::Prim => MazeGen::Prim(Box::new(PrimGen::new(maze))),
+        Generator::TruePrim => MazeGen::TruePrim(Box::new(PrimGen::weighted(rng, maze))),
+    }
+}
+
+/// mazes generated by the count layouts button
+const LAYOUTS_BATCH: u64 = 20;
+
+/// Generates mazes with the current settings from the seeds after the current one, then counts
+/// how many layouts they have. Small mazes and biased generators come out the same quickly
+fn debug_count_layouts(state: &mut GameState) {
+    let (rng, first) = (state.rng.clone(), state.seed);
+    let mut mazes = vec![];
+    for seed in (1..=LAYOUTS_BATCH).map(|i| first.wrapping_add(i)) {
+        state.rng = StdRng::seed_from_u64(seed);
+        let maze = debug_new_maze(state);
+        let mut generator = debug_generator(state.generator, &mut state.rng, maze);
+        let wilson = generator.maze_mut();
+        wilson.finish(&mut state.rng);
+        let mut maze = wilson.maze().clone();
+        maze.open_rooms(&mut StdRng::seed_from_u64(seed));
+        mazes.push(maze);
+    }
+    state.rng = rng;
+    println!(
+        "{} layouts out of {} mazes",
+        dedup(mazes).len(),
+        LAYOUTS_BATCH
+    );
+}
+
+/// Saves the generated (or loaded) maze with its rooms opened, without braiding nor transforms so
+/// it matches the generator and seed. JSON files hold the maze and its solution, see
+/// `schema/maze.schema.json`. Drawings and graphs are of the maze on screen instead, with its
+/// overlay
+fn debug_save_maze(state: &GameState) {
+    let maze = &debug_opened_maze(state);
+    let path = &state.debug.debug_save_path;
+    let saved = match state.debug.debug_save_format {
+        SaveFormat::Json(format) => {
+            let solved = serde_json::json!({
+                "maze": MazeDocument::new(maze, format),
+                "solution": Solution::new(maze, maze.middle_cell()),
+            });
+            fs::write(path, solved.to_string())
+        }
+        SaveFormat::Svg => {
+            let maze = state.braided.as_ref().unwrap_or(maze);
+            let [r, g, b] = state.debug.debug_borders_color;
+            let style = SvgStyle {
+                wall_color: Color { r, g, b },
+                ..SvgStyle::default()
+            };
+            let mut svg = Svg::new(maze).level(state.debug.debug_level).style(style);
+            match state.overlay {
+                Some(Overlay::Distances) => svg = svg.distances(&state.distances),
+                Some(Overlay::LongestPath) => svg = svg.path(&state.longest_path),
+                None => {}
+            }
+            if let (Some(start), Some(goal)) =
+                (state.longest_path.first(), state.longest_path.last())
+            {
+                svg = svg.markers(*start, *goal);
+            }
+            fs::write(path, svg.write())
+        }
+        SaveFormat::Png => {
+            let maze = state.braided.as_ref().unwrap_or(maze);
+            let [r, g, b] = state.debug.debug_borders_color;
+            let mut raster = Raster::new(maze)
+                .level(state.debug.debug_level)
+                .cell_size(state.debug.debug_png_cell_size)
+                .colors(Color { r: 0, g: 0, b: 0 }, Color { r, g, b });
+            if let Some(overlay) = state.overlay {
+                raster = raster.overlay(overlay, &state.distances, &state.longest_path);
+            }
+            raster.draw().save(path)
+        }
+        SaveFormat::Graph(format) => {
+            let maze = state.braided.as_ref().unwrap_or(maze);
+            let mut graph = Graph::new(maze);
+            if !state.distances.is_empty() {
+                graph = graph.distances(&state.distances);
+            }
+            fs::write(path, graph.write(format))
+        }
+        SaveFormat::Binary => SavedMaze {
+            maze: maze.clone(),
+            generator: state.wilson.generator(),
+            seed: state.seed,
+        }
+        .save(path),
+    };
+    match saved {
+        Ok(()) => println!("saved {}", path),
+        Err(e) => println!("could not save {}: {}", path, e),
+    }
+}
+
+/// Reads a maze drawn in ascii by the print button (`.txt`), scanned (`.png`) or saved in binary.
+/// It replaces the generated one until the next restart, the generator keeps going underneath
+fn debug_load_maze(state: &mut GameState) {
+    let path = &state.debug.debug_load_path;
+    let extension = Path::new(path).extension().and_then(|e| e.to_str());
+    let loaded = match extension {
+        Some("txt") => match fs::read_to_string(path) {
+            Ok(ascii) => Maze::from_ascii(&ascii).map_err(|e| format!("{:?}", e)),
+            Err(e) => Err(e.to_string()),
+        },
+        Some("png") => Maze::load_png(path).map_err(|e| format!("{:?}", e)),
+        _ => SavedMaze::load(path)
+            .map(|saved| {
+                println!(
+                    "generated by {:?} from seed {}",
+                    saved.generator, saved.seed
+                );
+                saved.maze
+            })
+            .map_err(|e| format!("{:?}", e)),
+    };
+    match loaded {
+        Ok(maze) => {
+            println!("loaded {}", path);
+            state.loaded = Some(maze);
+            state.braided = None;
+            state.debug.debug_level = 0;
+            state.debug.debug_braid = 0;
+            state.debug.debug_transform = None;
+        }
+        Err(e) => println!("could not load {}: {}", path, e),
+    }
+}
+
+/// Like `goto_step`, then validates the maze where it stops. Generators never leave broken
+/// links or loops behind, and the finished maze must be perfect. Stepping one at a time checks
+/// every step, while jumps and finishing only check where they land, validating is O(cells)
+fn debug_validated_goto_step(wilson: &mut dyn MazeGenerator, rng: &mut StdRng, step: usize) {
+    wilson.goto_step(rng, step);
+    let report = wilson.maze().validate();
+    let broken = if wilson.finished() {
+        !report.is_perfect()
+    } else {
+        !report.is_valid()
+    };
+    if broken {
+        println!("invalid maze at step {}", wilson.next_step());
+        for violation in &report.violations {
+            println!("  {:?}", violation);
+        }
+    }
+}
+
+/// Opens the rooms of a copy of the generated maze once it is generated, then braids and
+/// transforms it whenever the braid slider or the transform change. Loaded mazes are always
+/// copied, they are only shown through `braided`
+fn debug_braid_maze(state: &mut GameState) {
+    let wilson = state.wilson.maze();
+    let loaded = state.loaded.is_some();
+    let finished = wilson.finished() || loaded;
+    let (braid, transform) = if finished {
+        (state.debug.debug_braid, state.debug.debug_transform)
+    } else {
+        (0, None)
+    };
+    let rooms = finished && !wilson.maze().rooms().is_empty();
+    let copied = braid != 0 || transform.is_some() || rooms || loaded;
+    let changed = braid != state.braid || transform != state.transform;
+    if !changed && copied == state.braided.is_some() {
+        return;
+    }
+    state.braided = if copied {
+        let mut maze = debug_opened_maze(state);
+        if braid != 0 {
+            maze.braid(&mut state.rng, braid as f64 / 100.0);
+        }
+        // cylinders cannot be turned on their side
+        if let Some(transformed) = transform.and_then(|t| maze.transformed(t)) {
+            maze = transformed;
+        }
+        Some(maze)
+    } else {
+        None
+    };
+    state.braid = braid;
+    state.transform = transform;
+    state.distances.clear();
+    state.longest_path.clear();
+    state.stats = None;
+}
+
+/// The generated maze with its rooms opened, or the loaded one. Doors are picked from the seed,
+/// so the same seed always opens the same doors
+fn debug_opened_maze(state: &GameState) -> Maze {
+    let mut maze = match &state.loaded {
+        Some(maze) => maze.clone(),
+        None => state.wilson.maze().maze().clone(),
+    };
+    maze.open_rooms(&mut StdRng::seed_from_u64(state.seed));
+    maze
+}
+
+/// the mask (if any) decides the size of the maze. Polar mazes use the height as rings count
+/// and have a single level. Only square grids wrap
+fn debug_new_maze(state: &mut GameState) -> Maze {
+    if state.debug.debug_topology == Topology::Polar {
+        return Maze::polar(state.maze_height);
+    }
+    let path = &state.debug.debug_mask_path;
+    let mask = if path.is_empty() {
+        Mask::new(state.maze_width, state.maze_height)
+    } else {
+        match Mask::load(path) {
+            Ok(mask) => {
+                state.maze_width = mask.width();
+                state.maze_height = mask.height();
+                mask
+            }
+            Err(e) => {
+                println!("cannot load mask {}: {:?}", path, e);
+                Mask::new(state.maze_width, state.maze_height)
+            }
+        }
+    };
+    let mask = if Maze::connects(state.debug.debug_topology, &mask) {
+        mask
+    } else {
+        println!(
+            "mask {} splits the {:?} grid",
+            path, state.debug.debug_topology
+        );
+        Mask::new(state.maze_width, state.maze_height)
+    };
+    let maze = Maze::with_levels(
+        state.debug.debug_topology,
+        mask,
+        state.debug.debug_maze_levels,
+    );
+    let maze = if state.debug.debug_topology == Topology::Square {
+        maze.wrapped(state.debug.debug_wrap)
+    } else {
+        maze
+    };
+    let (count, doors) = (state.debug.debug_rooms, state.debug.debug_doors);
+    let rooms = debug_place_rooms(&mut state.rng, &maze, count, doors);
+    maze.with_rooms(rooms)
+}
+
+/// Up to `count` rooms of 2 to 4 cells a side, away from the edges and from each other so that
+/// corridors go all around them. Triangles only reach every other row, they need wider gaps, and
+/// masks can still leave cells that only a room would reach
+fn debug_place_rooms(rng: &mut StdRng, maze: &Maze, count: usize, doors: usize) -> Vec<Room> {
+    let gap = if maze.topology() == Topology::Triangle {
+        2
+    } else {
+        1
+    };
+    let mut rooms: Vec<Room> = vec![];
+    for _ in 0..count * 10 {
+        if rooms.len() == count {
+            break;
+        }
+        let (width, height) = (rng.gen_range(2..=4), rng.gen_range(2..=4));
+        if maze.width() < width + 2 * gap || maze.height() < height + 2 * gap {
+            continue;
+        }
+        let room = Room {
+            level: rng.gen_range(0..maze.levels()),
+            x: rng.gen_range(gap..=maze.width() - width - gap),
+            y: rng.gen_range(gap..=maze.height() - height - gap),
+            width,
+            height,
+            doors,
+        };
+        let apart = rooms.iter().all(|r| {
+            r.level != room.level
+                || r.x >= room.x + room.width + gap
+                || room.x >= r.x + r.width + gap
+                || r.y >= room.y + room.height + gap
+                || room.y >= r.y + r.height + gap
+        });
+        if apart {
+            rooms.push(room);
+            if !maze.fits_rooms(&rooms) {
+                rooms.pop();
+            }
+        }
+    }
+    rooms
 }
@@ -1,6 +1,33 @@
-use crate::maze::Maze;
+use crate::grid::Grid;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 
-pub fn shortest_path(from: usize, to: usize, maze: &Maze, distances: &[usize]) -> Vec<usize> {
+/// Distances and longest path of a maze, serializable for tools outside of the plugin
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Solution {
+    /// cell the distances are counted from
+    pub start: usize,
+    /// steps from `start` to every cell, `None` when it cannot be reached
+    pub distances: Vec<Option<usize>>,
+    /// see `longest_path`
+    pub longest_path: Vec<usize>,
+}
+
+impl Solution {
+    pub fn new<G: Grid>(maze: &G, start: usize) -> Self {
+        let distances = flood(start, maze)
+            .into_iter()
+            .map(|d| if d < maze.len() { Some(d) } else { None })
+            .collect();
+        Self {
+            start,
+            distances,
+            longest_path: longest_path(maze),
+        }
+    }
+}
+
+pub fn shortest_path<G: Grid>(from: usize, to: usize, maze: &G, distances: &[usize]) -> Vec<usize> {
     let mut breadcrumbs = vec![to];
     let mut current = to;
     while let Some(next) = maze
@@ -18,32 +45,43 @@ pub fn shortest_path(from: usize, to: usize, maze: &Maze, distances: &[usize]) -
     breadcrumbs
 }
 
-pub fn longest_path(maze: &Maze) -> Vec<usize> {
-    let distances = flood(0, maze);
+pub fn longest_path<G: Grid>(maze: &G) -> Vec<usize> {
+    let start = match maze.first_enabled() {
+        Some(start) => start,
+        None => return vec![],
+    };
+    let distances = flood(start, maze);
     let (from, _) = distances
         .iter()
         .enumerate()
+        .filter(|(_, dis)| **dis < maze.len())
         .max_by_key(|(_, dis)| *dis)
         .unwrap();
     let new_distances = flood(from, maze);
     let (to, _) = new_distances
         .iter()
         .enumerate()
+        .filter(|(_, dis)| **dis < maze.len())
         .max_by_key(|(_, dis)| *dis)
         .unwrap();
     shortest_path(from, to, maze, &new_distances)
 }
 
-pub fn flood(from: usize, maze: &Maze) -> Vec<usize> {
-    let max_distance = maze.cells().len();
-    let mut distances = vec![max_distance; maze.cells().len()];
-    let mut pending = vec![(from, 1)];
+/// distance from `from` to every cell. Cells that cannot be reached (e.g. masked ones) get
+/// `maze.len()`
+pub fn flood<G: Grid>(from: usize, maze: &G) -> Vec<usize> {
+    let max_distance = maze.len();
+    let mut distances = vec![max_distance; maze.len()];
+    // breadth first, so every cell is reached through its shortest path first
+    let mut pending = VecDeque::new();
+    pending.push_back(from);
     distances[from] = 0;
-    while let Some((current, distance)) = pending.pop() {
+    while let Some(current) = pending.pop_front() {
+        let distance = distances[current] + 1;
         for neighbor in maze.linked_neighbors(current).iter() {
-            if distances[neighbor.idx] == max_distance || distances[neighbor.idx] > distance {
+            if distances[neighbor.idx] == max_distance {
                 distances[neighbor.idx] = distance;
-                pending.push((neighbor.idx, distance + 1));
+                pending.push_back(neighbor.idx);
             }
         }
     }
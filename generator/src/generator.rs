@@ -1,25 +1,35 @@
-use crate::{maze::Maze, render::render_borders};
+use crate::{grid::Grid, maze::Maze, render::RenderGrid};
 use host_api::{Color, RenderGroup};
 use rand::prelude::StdRng;
 
+mod aldous_broder;
 mod binary_tree;
 mod hunt_and_kill;
+mod kruskal;
+mod prim;
 mod recur_backtracker;
 mod sidewinder;
 mod wilson;
 
+pub use self::aldous_broder::AldousBroderGen;
 pub use self::binary_tree::BinaryTreeGen;
 pub use self::hunt_and_kill::HuntAndKillGen;
+pub use self::kruskal::KruskalGen;
+pub use self::prim::PrimGen;
 pub use self::recur_backtracker::RecurBacktrackerGen;
 pub use self::sidewinder::SidewinderGen;
 pub use self::wilson::WilsonGen;
 
-pub trait MazeGenerator {
-    fn render(&mut self, render_group: &mut RenderGroup, border_color: Color) {
-        for y in 0..self.maze().height() {
-            for x in 0..self.maze().width() {
-                render_borders(render_group, x, y, self.maze(), border_color);
-            }
+/// Generates a maze on any kind of grid, one step at a time
+pub trait MazeGenerator<G: Grid = Maze> {
+    /// draws a single level of the maze
+    fn render(&mut self, render_group: &mut RenderGroup, border_color: Color, level: usize)
+    where
+        G: RenderGrid,
+    {
+        for cell in 0..self.maze().len() {
+            self.maze()
+                .render_borders(render_group, level, cell, border_color);
         }
     }
     fn next(&mut self, rng: &mut StdRng);
@@ -33,7 +43,7 @@ pub trait MazeGenerator {
 
     fn steps_count(&self) -> usize;
     fn next_step(&self) -> usize;
-    fn maze(&self) -> &Maze;
+    fn maze(&self) -> &G;
 
     fn finish(&mut self, rng: &mut StdRng) {
         while !self.finished() {
@@ -54,13 +64,17 @@ pub trait MazeGenerator {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Generator {
     BinaryTree,
     Sidewind,
     Wilson,
     HuntAndKill,
     RecurBacktracker,
+    AldousBroder,
+    Kruskal,
+    Prim,
+    TruePrim,
 }
 
 pub enum MazeGen {
@@ -69,6 +83,10 @@ pub enum MazeGen {
     Wilson(Box<WilsonGen>),
     HuntAndKill(Box<HuntAndKillGen>),
     RecurBacktracker(Box<RecurBacktrackerGen>),
+    AldousBroder(Box<AldousBroderGen>),
+    Kruskal(Box<KruskalGen>),
+    Prim(Box<PrimGen>),
+    TruePrim(Box<PrimGen>),
 }
 
 // NOTE(chrde): this is a workaround since we cant have trait objects in our state - live reload doesnt work
@@ -80,6 +98,24 @@ impl MazeGen {
             MazeGen::Wilson(g) => g.as_ref() as &dyn MazeGenerator,
             MazeGen::HuntAndKill(g) => g.as_ref() as &dyn MazeGenerator,
             MazeGen::RecurBacktracker(g) => g.as_ref() as &dyn MazeGenerator,
+            MazeGen::AldousBroder(g) => g.as_ref() as &dyn MazeGenerator,
+            MazeGen::Kruskal(g) => g.as_ref() as &dyn MazeGenerator,
+            MazeGen::Prim(g) | MazeGen::TruePrim(g) => g.as_ref() as &dyn MazeGenerator,
+        }
+    }
+
+    /// the generator that built this one, the combo box may have changed since
+    pub fn generator(&self) -> Generator {
+        match self {
+            MazeGen::BinaryTree(_) => Generator::BinaryTree,
+            MazeGen::Sidewind(_) => Generator::Sidewind,
+            MazeGen::Wilson(_) => Generator::Wilson,
+            MazeGen::HuntAndKill(_) => Generator::HuntAndKill,
+            MazeGen::RecurBacktracker(_) => Generator::RecurBacktracker,
+            MazeGen::AldousBroder(_) => Generator::AldousBroder,
+            MazeGen::Kruskal(_) => Generator::Kruskal,
+            MazeGen::Prim(_) => Generator::Prim,
+            MazeGen::TruePrim(_) => Generator::TruePrim,
         }
     }
 
@@ -90,6 +126,9 @@ impl MazeGen {
             MazeGen::Wilson(g) => g.as_mut() as &mut dyn MazeGenerator,
             MazeGen::HuntAndKill(g) => g.as_mut() as &mut dyn MazeGenerator,
             MazeGen::RecurBacktracker(g) => g.as_mut() as &mut dyn MazeGenerator,
+            MazeGen::AldousBroder(g) => g.as_mut() as &mut dyn MazeGenerator,
+            MazeGen::Kruskal(g) => g.as_mut() as &mut dyn MazeGenerator,
+            MazeGen::Prim(g) | MazeGen::TruePrim(g) => g.as_mut() as &mut dyn MazeGenerator,
         }
     }
 }
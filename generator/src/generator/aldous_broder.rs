@@ -0,0 +1,198 @@
+use super::MazeGenerator;
+use crate::{
+    grid::Grid,
+    maze::{Maze, Neighbor1},
+    render::{RenderGrid, DARK_RED, RED},
+};
+use host_api::{Color, RenderGroup};
+use rand::prelude::{IteratorRandom, StdRng};
+
+#[derive(Copy, Clone, Debug)]
+enum Step {
+    Empty,
+    Start(usize),
+    /// to a neighbor that was already visited
+    Walk(usize, Neighbor1),
+    /// to a neighbor visited for the first time, carving the passage
+    Link(usize, Neighbor1),
+    Finished,
+}
+
+/// Random walk that links every cell the first time it is reached. Uniform like wilson, but
+/// slow to find the last cells
+pub struct AldousBroderGen<G = Maze> {
+    maze: G,
+    visited: Vec<bool>,
+    unvisited: usize,
+    steps: Vec<Step>,
+    next: usize,
+}
+
+impl<G: Grid> AldousBroderGen<G> {
+    pub fn new(maze: G) -> Self {
+        let unvisited = (0..maze.len()).filter(|c| maze.is_enabled(*c)).count();
+        Self {
+            visited: vec![false; maze.len()],
+            maze,
+            unvisited,
+            next: 0,
+            steps: vec![Step::Empty],
+        }
+    }
+
+    /// the walker moves to a random neighbor, visited or not
+    fn walk(&self, rng: &mut StdRng, cell: usize) -> Step {
+        if self.unvisited == 0 {
+            return Step::Finished;
+        }
+        let next = self
+            .maze
+            .neighbors(cell)
+            .iter()
+            .choose(rng)
+            .expect("enabled cells must be connected");
+        if self.visited[next.idx] {
+            Step::Walk(cell, next)
+        } else {
+            Step::Link(cell, next)
+        }
+    }
+
+    fn visit(&mut self, cell: usize) {
+        self.visited[cell] = true;
+        self.unvisited -= 1;
+    }
+
+    fn unvisit(&mut self, cell: usize) {
+        self.visited[cell] = false;
+        self.unvisited += 1;
+    }
+}
+
+impl<G: Grid> MazeGenerator<G> for AldousBroderGen<G> {
+    fn render(&mut self, render_group: &mut RenderGroup, border_color: Color, level: usize)
+    where
+        G: RenderGrid,
+    {
+        if !matches!(self.steps[self.next], Step::Finished) {
+            for cell in (0..self.maze.len()).filter(|c| self.visited[*c]) {
+                let color = Color {
+                    r: 34,
+                    g: 70,
+                    b: 70,
+                };
+                self.maze.render_cell(render_group, level, cell, color);
+            }
+        }
+        match self.steps[self.next] {
+            Step::Empty | Step::Finished => {}
+            Step::Start(cell) => self.maze.render_cell(render_group, level, cell, RED),
+            Step::Walk(cell, next) | Step::Link(cell, next) => {
+                self.maze.render_cell(render_group, level, cell, DARK_RED);
+                self.maze.render_cell(render_group, level, next.idx, RED);
+            }
+        }
+        for cell in 0..self.maze.len() {
+            self.maze
+                .render_borders(render_group, level, cell, border_color);
+        }
+    }
+
+    fn next(&mut self, rng: &mut StdRng) {
+        let next = match self.steps[self.next] {
+            Step::Empty => {
+                let start = (0..self.maze.len())
+                    .filter(|c| self.maze.is_enabled(*c))
+                    .choose(rng);
+                match start {
+                    Some(cell) => Step::Start(cell),
+                    None => Step::Finished,
+                }
+            }
+            Step::Start(cell) => {
+                self.visit(cell);
+                self.walk(rng, cell)
+            }
+            Step::Walk(_, next) => self.walk(rng, next.idx),
+            Step::Link(cell, next) => {
+                self.maze.link(cell, next.dir);
+                self.visit(next.idx);
+                self.walk(rng, next.idx)
+            }
+            Step::Finished => {
+                return;
+            }
+        };
+        // when replaying, the step is already there
+        if self.next == self.steps.len() - 1 {
+            self.steps.push(next);
+        }
+        self.next += 1;
+    }
+
+    fn prev(&mut self) {
+        if self.next == 0 {
+            return;
+        }
+        self.next -= 1;
+        match self.steps[self.next] {
+            Step::Empty => {}
+            Step::Start(cell) => self.unvisit(cell),
+            Step::Walk(_, _) => {}
+            Step::Link(cell, next) => {
+                self.maze.unlink(cell, next.dir);
+                self.unvisit(next.idx);
+            }
+            Step::Finished => {}
+        }
+    }
+
+    fn finished(&self) -> bool {
+        matches!(self.steps[self.next], Step::Finished)
+    }
+
+    fn completed(&self) -> bool {
+        self.steps
+            .last()
+            .map_or(false, |s| matches!(s, Step::Finished))
+    }
+
+    fn steps_count(&self) -> usize {
+        self.steps.len()
+    }
+
+    fn next_step(&self) -> usize {
+        self.next
+    }
+
+    fn maze(&self) -> &G {
+        &self.maze
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn replays() {
+        let rng = &mut StdRng::seed_from_u64(5);
+        let mut generator = AldousBroderGen::new(Maze::new(6, 5));
+        generator.finish(rng);
+        let finished = generator.maze().clone();
+        let steps = generator.steps_count();
+        assert_eq!(generator.unvisited, 0);
+        assert!(finished.validate().is_perfect());
+
+        generator.goto_step(rng, 0);
+        assert!(generator.maze() == &Maze::new(6, 5));
+        assert!(generator.visited.iter().all(|v| !v));
+        assert_eq!(generator.unvisited, 30);
+
+        generator.finish(rng);
+        assert!(generator.maze() == &finished);
+        assert_eq!(generator.steps_count(), steps);
+        assert_eq!(generator.unvisited, 0);
+    }
+}
@@ -1,4 +1,5 @@
 use super::MazeGenerator;
+use crate::grid::Grid;
 use crate::maze::{Maze, Neighbor, Neighbor1};
 use rand::prelude::StdRng;
 use rand::Rng;
@@ -11,31 +12,33 @@ enum Step {
     Finished,
 }
 
-pub struct BinaryTreeGen {
-    maze: Maze,
+pub struct BinaryTreeGen<G = Maze> {
+    maze: G,
     next: usize,
     steps: Vec<Step>,
 }
 
-impl BinaryTreeGen {
-    pub fn new(width: usize, height: usize) -> Self {
+impl<G: Grid> BinaryTreeGen<G> {
+    pub fn new(maze: G) -> Self {
         Self {
-            maze: Maze::new(width, height),
+            maze,
             next: 0,
             steps: vec![Step::Empty],
         }
     }
 }
 
-impl MazeGenerator for BinaryTreeGen {
+impl<G: Grid> MazeGenerator<G> for BinaryTreeGen<G> {
     fn next(&mut self, rng: &mut StdRng) {
         let next = match self.steps[self.next] {
             Step::Empty => Step::Direction(0),
+            Step::Direction(cell) if cell == self.maze.len() => Step::Finished,
             Step::Direction(cell) => {
-                let neighbors: Vec<_> = [Neighbor::North, Neighbor::East]
+                // going up too keeps the levels connected, wrapping around would close loops
+                let neighbors: Vec<_> = [Neighbor::North, Neighbor::East, Neighbor::Up]
                     .iter()
                     .cloned()
-                    .filter_map(|n| self.maze.neighbor_at(cell, n))
+                    .filter_map(|n| self.maze.unwrapped_neighbor_at(cell, n))
                     .collect();
                 if neighbors.is_empty() {
                     Step::Direction(cell + 1)
@@ -46,11 +49,7 @@ impl MazeGenerator for BinaryTreeGen {
             }
             Step::Link(cell, next) => {
                 self.maze.link(cell, next.dir);
-                if cell == self.maze.len() - 1 {
-                    Step::Finished
-                } else {
-                    Step::Direction(cell + 1)
-                }
+                Step::Direction(cell + 1)
             }
             Step::Finished => {
                 return;
@@ -93,7 +92,7 @@ impl MazeGenerator for BinaryTreeGen {
         self.next
     }
 
-    fn maze(&self) -> &Maze {
+    fn maze(&self) -> &G {
         &self.maze
     }
 
@@ -1,7 +1,8 @@
 use super::MazeGenerator;
 use crate::{
+    grid::Grid,
     maze::{Maze, Neighbor1},
-    render::{render_borders, render_cell, RED},
+    render::{RenderGrid, RED},
 };
 use rand::prelude::{IteratorRandom, StdRng};
 
@@ -10,30 +11,39 @@ enum Step {
     Empty,
     Walk(usize),
     Link(usize, Neighbor1),
+    Tunnel(usize, Neighbor1),
     Finished,
 }
 
-pub struct HuntAndKillGen {
-    maze: Maze,
+pub struct HuntAndKillGen<G = Maze> {
+    maze: G,
     steps: Vec<Step>,
     next: usize,
 }
 
-impl HuntAndKillGen {
-    pub fn new(width: usize, height: usize) -> Self {
+impl<G: Grid> HuntAndKillGen<G> {
+    pub fn new(maze: G) -> Self {
         Self {
-            maze: Maze::new(width, height),
+            maze,
             next: 0,
             steps: vec![Step::Empty],
         }
     }
 
-    /// Go to a non-yet visited neighbor
-    pub fn unvisited_neighbor(&self, rng: &mut StdRng, cell: usize) -> Option<Neighbor1> {
+    /// Go to a non-yet visited neighbor, possibly tunneling under a corridor on weave mazes
+    fn unvisited_neighbor(&self, rng: &mut StdRng, cell: usize) -> Option<Step> {
+        let tunnels = self.maze.tunnel_neighbors(cell);
         self.maze
             .neighbors(cell)
             .iter()
-            .filter(|n| self.maze.cell(n.idx).links().is_empty())
+            .filter(|n| self.maze.links(n.idx).is_empty())
+            .map(|n| Step::Link(cell, n))
+            .chain(
+                tunnels
+                    .into_iter()
+                    .filter(|n| self.maze.links(n.idx).is_empty())
+                    .map(|n| Step::Tunnel(cell, n)),
+            )
             .choose(rng)
     }
 
@@ -42,27 +52,26 @@ impl HuntAndKillGen {
         self.maze
             .neighbors(cell)
             .iter()
-            .filter(|n| self.maze.cell(n.idx).links().is_partial())
+            .filter(|n| !self.maze.links(n.idx).is_empty())
             .choose(rng)
             .map(|n| (cell, n))
     }
 }
 
-impl MazeGenerator for HuntAndKillGen {
+impl<G: Grid> MazeGenerator<G> for HuntAndKillGen<G> {
     fn next(&mut self, rng: &mut StdRng) {
         let next = match self.steps[self.next] {
-            Step::Empty => Step::Walk(0),
+            Step::Empty => match self.maze.first_enabled() {
+                Some(cell) => Step::Walk(cell),
+                None => Step::Finished,
+            },
             Step::Walk(cell) => {
-                if let Some(n) = self.unvisited_neighbor(rng, cell) {
-                    Step::Link(cell, n)
+                if let Some(step) = self.unvisited_neighbor(rng, cell) {
+                    step
                 } else {
-                    let candidate = self
-                        .maze
-                        .cells()
-                        .iter()
-                        .enumerate()
-                        .filter(|(_, cell)| cell.links().is_empty())
-                        .find_map(|(idx, _)| self.neighbor_with_link(rng, idx));
+                    let candidate = (0..self.maze.len())
+                        .filter(|cell| self.maze.links(*cell).is_empty())
+                        .find_map(|cell| self.neighbor_with_link(rng, cell));
                     match candidate {
                         Some((cell, next)) => Step::Link(cell, next),
                         None => Step::Finished,
@@ -73,6 +82,10 @@ impl MazeGenerator for HuntAndKillGen {
                 self.maze.link(cell, next.dir);
                 Step::Walk(next.idx)
             }
+            Step::Tunnel(cell, next) => {
+                self.maze.tunnel(cell, next.dir);
+                Step::Walk(next.idx)
+            }
             Step::Finished => {
                 return;
             }
@@ -97,6 +110,7 @@ impl MazeGenerator for HuntAndKillGen {
             Step::Empty => {}
             Step::Walk(_) => {}
             Step::Link(cell, next) => self.maze.unlink(cell, next.dir),
+            Step::Tunnel(cell, next) => self.maze.untunnel(cell, next.dir),
             Step::Finished => {}
         }
     }
@@ -119,27 +133,27 @@ impl MazeGenerator for HuntAndKillGen {
         self.next
     }
 
-    fn maze(&self) -> &Maze {
+    fn maze(&self) -> &G {
         &self.maze
     }
 
-    fn render(&mut self, render_group: &mut host_api::RenderGroup, border_color: host_api::Color) {
-        for y in 0..self.maze().height() {
-            for x in 0..self.maze().width() {
-                render_borders(render_group, x, y, self.maze(), border_color);
-            }
+    fn render(
+        &mut self,
+        render_group: &mut host_api::RenderGroup,
+        border_color: host_api::Color,
+        level: usize,
+    ) where
+        G: RenderGrid,
+    {
+        for cell in 0..self.maze.len() {
+            self.maze
+                .render_borders(render_group, level, cell, border_color);
         }
         match self.steps[self.next] {
             Step::Empty => {}
-            Step::Walk(cell) => {
-                let x = cell % self.maze.width();
-                let y = cell / self.maze.width();
-                render_cell(render_group, x, y, RED)
-            }
-            Step::Link(cell, _) => {
-                let x = cell % self.maze.width();
-                let y = cell / self.maze.width();
-                render_cell(render_group, x, y, RED)
+            Step::Walk(cell) => self.maze.render_cell(render_group, level, cell, RED),
+            Step::Link(cell, _) | Step::Tunnel(cell, _) => {
+                self.maze.render_cell(render_group, level, cell, RED)
             }
             Step::Finished => {}
         }
@@ -0,0 +1,253 @@
+use super::MazeGenerator;
+use crate::{
+    grid::Grid,
+    maze::{Maze, Neighbor1},
+    render::{RenderGrid, DARK_RED, RED},
+};
+use host_api::{Color, RenderGroup};
+use rand::prelude::{SliceRandom, StdRng};
+
+#[derive(Copy, Clone, Debug)]
+enum Step {
+    Empty,
+    /// the wall joins two sets, it is removed
+    Link(usize, Neighbor1),
+    /// both sides are already connected, the wall stays
+    Skip(usize, Neighbor1),
+    Finished,
+}
+
+/// Union-find by size without path compression, so that every union can be undone
+struct Sets {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+    /// roots merged into another set, the last one first
+    merged: Vec<usize>,
+}
+
+impl Sets {
+    fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+            size: vec![1; len],
+            merged: vec![],
+        }
+    }
+
+    fn find(&self, mut cell: usize) -> usize {
+        while self.parent[cell] != cell {
+            cell = self.parent[cell];
+        }
+        cell
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        let (root, child) = if self.size[a] < self.size[b] {
+            (b, a)
+        } else {
+            (a, b)
+        };
+        self.parent[child] = root;
+        self.size[root] += self.size[child];
+        self.merged.push(child);
+    }
+
+    fn undo(&mut self) {
+        let child = self.merged.pop().expect("no union to undo");
+        let root = self.parent[child];
+        self.parent[child] = child;
+        self.size[root] -= self.size[child];
+    }
+}
+
+/// Removes the walls in random order, unless both sides are already connected
+pub struct KruskalGen<G = Maze> {
+    maze: G,
+    /// every wall between enabled cells, in the order they are considered
+    walls: Vec<(usize, Neighbor1)>,
+    next_wall: usize,
+    sets: Sets,
+    enabled: usize,
+    steps: Vec<Step>,
+    next: usize,
+}
+
+impl<G: Grid> KruskalGen<G> {
+    pub fn new(rng: &mut StdRng, maze: G) -> Self {
+        let mut walls = vec![];
+        let enabled: Vec<_> = (0..maze.len()).filter(|c| maze.is_enabled(*c)).collect();
+        for cell in enabled.iter().cloned() {
+            for n in maze.neighbors(cell).iter() {
+                if cell < n.idx {
+                    walls.push((cell, n));
+                }
+            }
+        }
+        walls.shuffle(rng);
+        Self {
+            sets: Sets::new(maze.len()),
+            enabled: enabled.len(),
+            maze,
+            walls,
+            next_wall: 0,
+            next: 0,
+            steps: vec![Step::Empty],
+        }
+    }
+
+    /// what to do with the next wall, done once every cell is in the same set
+    fn consider(&self) -> Step {
+        if self.sets.merged.len() + 1 >= self.enabled || self.next_wall == self.walls.len() {
+            return Step::Finished;
+        }
+        let (cell, next) = self.walls[self.next_wall];
+        if self.sets.find(cell) == self.sets.find(next.idx) {
+            Step::Skip(cell, next)
+        } else {
+            Step::Link(cell, next)
+        }
+    }
+}
+
+/// Colors far apart for sets next to each other, dark enough to see the borders
+fn set_color(root: usize) -> Color {
+    // golden ratio turns
+    let hue = (root as f32 * 0.618_034).fract() * 6.0;
+    let (high, low) = (150.0, 50.0);
+    let mid = low + (high - low) * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as usize {
+        0 => (high, mid, low),
+        1 => (mid, high, low),
+        2 => (low, high, mid),
+        3 => (low, mid, high),
+        4 => (mid, low, high),
+        _ => (high, low, mid),
+    };
+    Color {
+        r: r as u8,
+        g: g as u8,
+        b: b as u8,
+    }
+}
+
+impl<G: Grid> MazeGenerator<G> for KruskalGen<G> {
+    fn render(&mut self, render_group: &mut RenderGroup, border_color: Color, level: usize)
+    where
+        G: RenderGrid,
+    {
+        if !matches!(self.steps[self.next], Step::Finished) {
+            // single cells are left alone, only the growing trees are colored
+            for cell in 0..self.maze.len() {
+                let root = self.sets.find(cell);
+                if self.sets.size[root] > 1 {
+                    self.maze
+                        .render_cell(render_group, level, cell, set_color(root));
+                }
+            }
+        }
+        match self.steps[self.next] {
+            Step::Empty | Step::Finished => {}
+            Step::Link(cell, next) | Step::Skip(cell, next) => {
+                self.maze.render_cell(render_group, level, cell, DARK_RED);
+                self.maze.render_cell(render_group, level, next.idx, RED);
+            }
+        }
+        for cell in 0..self.maze.len() {
+            self.maze
+                .render_borders(render_group, level, cell, border_color);
+        }
+    }
+
+    fn next(&mut self, _rng: &mut StdRng) {
+        let next = match self.steps[self.next] {
+            Step::Empty => self.consider(),
+            Step::Link(cell, next) => {
+                self.maze.link(cell, next.dir);
+                self.sets.union(cell, next.idx);
+                self.next_wall += 1;
+                self.consider()
+            }
+            Step::Skip(_, _) => {
+                self.next_wall += 1;
+                self.consider()
+            }
+            Step::Finished => {
+                return;
+            }
+        };
+        // when replaying, the step is already there
+        if self.next == self.steps.len() - 1 {
+            self.steps.push(next);
+        }
+        self.next += 1;
+    }
+
+    fn prev(&mut self) {
+        if self.next == 0 {
+            return;
+        }
+        self.next -= 1;
+        match self.steps[self.next] {
+            Step::Empty => {}
+            Step::Link(cell, next) => {
+                self.maze.unlink(cell, next.dir);
+                self.sets.undo();
+                self.next_wall -= 1;
+            }
+            Step::Skip(_, _) => self.next_wall -= 1,
+            Step::Finished => {}
+        }
+    }
+
+    fn finished(&self) -> bool {
+        matches!(self.steps[self.next], Step::Finished)
+    }
+
+    fn completed(&self) -> bool {
+        self.steps
+            .last()
+            .map_or(false, |s| matches!(s, Step::Finished))
+    }
+
+    fn steps_count(&self) -> usize {
+        self.steps.len()
+    }
+
+    fn next_step(&self) -> usize {
+        self.next
+    }
+
+    fn maze(&self) -> &G {
+        &self.maze
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn replays() {
+        let rng = &mut StdRng::seed_from_u64(5);
+        let mut generator = KruskalGen::new(rng, Maze::new(6, 5));
+        generator.finish(rng);
+        let finished = generator.maze().clone();
+        let (steps, next_wall) = (generator.steps_count(), generator.next_wall);
+        assert_eq!(generator.sets.merged.len(), 29);
+        assert!(finished.validate().is_perfect());
+
+        generator.goto_step(rng, 0);
+        assert!(generator.maze() == &Maze::new(6, 5));
+        assert_eq!(generator.next_wall, 0);
+        assert!(generator.sets.merged.is_empty());
+        assert!((0..30).all(|c| generator.sets.find(c) == c && generator.sets.size[c] == 1));
+
+        generator.finish(rng);
+        assert!(generator.maze() == &finished);
+        assert_eq!(generator.steps_count(), steps);
+        assert_eq!(generator.next_wall, next_wall);
+        assert_eq!(generator.sets.merged.len(), 29);
+    }
+}
@@ -0,0 +1,265 @@
+use super::MazeGenerator;
+use crate::{
+    grid::Grid,
+    maze::{Maze, Neighbor1},
+    render::{RenderGrid, DARK_RED, RED},
+};
+use host_api::{Color, RenderGroup};
+use rand::prelude::{IteratorRandom, Rng, StdRng};
+use std::collections::BTreeSet;
+
+#[derive(Copy, Clone, Debug)]
+enum Step {
+    Empty,
+    Start(usize),
+    /// an active cell reaches one of its unvisited neighbors, which becomes active too
+    Link(usize, Neighbor1),
+    /// the active cell at that place in the frontier has no unvisited neighbor left, the place
+    /// is only kept by the simplified version
+    Retire(usize, usize),
+    Finished,
+}
+
+/// Grows the maze from the frontier of active cells, which spreads out from the start in
+/// every direction. The simplified version expands random cells, the true one gives every
+/// cell a random weight and always expands the lightest ones
+pub struct PrimGen<G = Maze> {
+    maze: G,
+    /// empty for the simplified version
+    weights: Vec<u32>,
+    visited: Vec<bool>,
+    /// visited cells that may still have unvisited neighbors, for the simplified version
+    active: Vec<usize>,
+    /// the same with their weights for the true version, the lightest first
+    lightest: BTreeSet<(u32, usize)>,
+    steps: Vec<Step>,
+    next: usize,
+}
+
+impl<G: Grid> PrimGen<G> {
+    pub fn new(maze: G) -> Self {
+        Self {
+            weights: vec![],
+            visited: vec![false; maze.len()],
+            maze,
+            active: vec![],
+            lightest: BTreeSet::new(),
+            next: 0,
+            steps: vec![Step::Empty],
+        }
+    }
+
+    pub fn weighted(rng: &mut StdRng, maze: G) -> Self {
+        let weights = (0..maze.len()).map(|_| rng.gen()).collect();
+        Self {
+            weights,
+            ..Self::new(maze)
+        }
+    }
+
+    fn activate(&mut self, cell: usize) {
+        if self.weights.is_empty() {
+            self.active.push(cell);
+        } else {
+            self.lightest.insert((self.weights[cell], cell));
+        }
+    }
+
+    /// undoes `activate`
+    fn deactivate(&mut self, cell: usize) {
+        if self.weights.is_empty() {
+            self.active.pop();
+        } else {
+            self.lightest.remove(&(self.weights[cell], cell));
+        }
+    }
+
+    fn pick(&self, rng: &mut StdRng) -> Step {
+        let weighted = !self.weights.is_empty();
+        let (idx, cell) = if weighted {
+            match self.lightest.iter().next() {
+                Some((_, cell)) => (0, *cell),
+                None => return Step::Finished,
+            }
+        } else if self.active.is_empty() {
+            return Step::Finished;
+        } else {
+            let idx = rng.gen_range(0..self.active.len());
+            (idx, self.active[idx])
+        };
+        let neighbors = self.maze.neighbors(cell);
+        let unvisited = neighbors.iter().filter(|n| !self.visited[n.idx]);
+        let next = if weighted {
+            unvisited.min_by_key(|n| self.weights[n.idx])
+        } else {
+            unvisited.choose(rng)
+        };
+        match next {
+            Some(next) => Step::Link(cell, next),
+            None => Step::Retire(idx, cell),
+        }
+    }
+}
+
+impl<G: Grid> MazeGenerator<G> for PrimGen<G> {
+    fn render(&mut self, render_group: &mut RenderGroup, border_color: Color, level: usize)
+    where
+        G: RenderGrid,
+    {
+        if !matches!(self.steps[self.next], Step::Finished) {
+            for cell in (0..self.maze.len()).filter(|c| self.visited[*c]) {
+                let color = Color {
+                    r: 34,
+                    g: 70,
+                    b: 70,
+                };
+                self.maze.render_cell(render_group, level, cell, color);
+            }
+            let lightest = self.lightest.iter().map(|(_, cell)| cell);
+            for cell in self.active.iter().chain(lightest) {
+                let color = Color {
+                    r: 120,
+                    g: 80,
+                    b: 105,
+                };
+                self.maze.render_cell(render_group, level, *cell, color);
+            }
+        }
+        match self.steps[self.next] {
+            Step::Empty | Step::Finished => {}
+            Step::Start(cell) | Step::Retire(_, cell) => {
+                self.maze.render_cell(render_group, level, cell, RED)
+            }
+            Step::Link(cell, next) => {
+                self.maze.render_cell(render_group, level, cell, DARK_RED);
+                self.maze.render_cell(render_group, level, next.idx, RED);
+            }
+        }
+        for cell in 0..self.maze.len() {
+            self.maze
+                .render_borders(render_group, level, cell, border_color);
+        }
+    }
+
+    fn next(&mut self, rng: &mut StdRng) {
+        let next = match self.steps[self.next] {
+            Step::Empty => {
+                let start = (0..self.maze.len())
+                    .filter(|c| self.maze.is_enabled(*c))
+                    .choose(rng);
+                match start {
+                    Some(cell) => Step::Start(cell),
+                    None => Step::Finished,
+                }
+            }
+            Step::Start(cell) => {
+                self.visited[cell] = true;
+                self.activate(cell);
+                self.pick(rng)
+            }
+            Step::Link(cell, next) => {
+                self.maze.link(cell, next.dir);
+                self.visited[next.idx] = true;
+                self.activate(next.idx);
+                self.pick(rng)
+            }
+            Step::Retire(idx, cell) => {
+                if self.weights.is_empty() {
+                    self.active.swap_remove(idx);
+                } else {
+                    self.lightest.remove(&(self.weights[cell], cell));
+                }
+                self.pick(rng)
+            }
+            Step::Finished => {
+                return;
+            }
+        };
+        // when replaying, the step is already there
+        if self.next == self.steps.len() - 1 {
+            self.steps.push(next);
+        }
+        self.next += 1;
+    }
+
+    fn prev(&mut self) {
+        if self.next == 0 {
+            return;
+        }
+        self.next -= 1;
+        match self.steps[self.next] {
+            Step::Empty => {}
+            Step::Start(cell) => {
+                self.deactivate(cell);
+                self.visited[cell] = false;
+            }
+            Step::Link(cell, next) => {
+                self.deactivate(next.idx);
+                self.visited[next.idx] = false;
+                self.maze.unlink(cell, next.dir);
+            }
+            Step::Retire(idx, cell) => {
+                if self.weights.is_empty() {
+                    let last = self.active.len();
+                    self.active.push(cell);
+                    self.active.swap(idx, last);
+                } else {
+                    self.activate(cell);
+                }
+            }
+            Step::Finished => {}
+        }
+    }
+
+    fn finished(&self) -> bool {
+        matches!(self.steps[self.next], Step::Finished)
+    }
+
+    fn completed(&self) -> bool {
+        self.steps
+            .last()
+            .map_or(false, |s| matches!(s, Step::Finished))
+    }
+
+    fn steps_count(&self) -> usize {
+        self.steps.len()
+    }
+
+    fn next_step(&self) -> usize {
+        self.next
+    }
+
+    fn maze(&self) -> &G {
+        &self.maze
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn replays() {
+        let rng = &mut StdRng::seed_from_u64(5);
+        let simplified = PrimGen::new(Maze::new(6, 5));
+        let weighted = PrimGen::weighted(rng, Maze::new(6, 5));
+        for mut generator in vec![simplified, weighted] {
+            generator.finish(rng);
+            let finished = generator.maze().clone();
+            let steps = generator.steps_count();
+            assert!(finished.validate().is_perfect());
+            assert!(generator.active.is_empty() && generator.lightest.is_empty());
+
+            generator.goto_step(rng, 0);
+            assert!(generator.maze() == &Maze::new(6, 5));
+            assert!(generator.visited.iter().all(|v| !v));
+            assert!(generator.active.is_empty() && generator.lightest.is_empty());
+
+            generator.finish(rng);
+            assert!(generator.maze() == &finished);
+            assert_eq!(generator.steps_count(), steps);
+            assert!(generator.visited.iter().all(|v| *v));
+        }
+    }
+}
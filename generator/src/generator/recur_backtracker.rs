@@ -1,4 +1,5 @@
 use super::MazeGenerator;
+use crate::grid::Grid;
 use crate::maze::{Maze, Neighbor, Neighbor1};
 use rand::prelude::{IteratorRandom, StdRng};
 use rand::Rng;
@@ -9,45 +10,57 @@ enum Step {
     Walk(usize),
     BackTrack,
     Link(usize, Neighbor1),
+    Tunnel(usize, Neighbor1),
     Finished,
 }
 
-pub struct RecurBacktrackerGen {
-    maze: Maze,
+pub struct RecurBacktrackerGen<G = Maze> {
+    maze: G,
     next: usize,
     stack: Vec<usize>,
     completed: Vec<usize>,
     steps: Vec<Step>,
 }
 
-impl RecurBacktrackerGen {
-    pub fn new(width: usize, height: usize) -> Self {
+impl<G: Grid> RecurBacktrackerGen<G> {
+    pub fn new(maze: G) -> Self {
         Self {
             stack: vec![],
             completed: vec![],
-            maze: Maze::new(width, height),
+            maze,
             next: 0,
             steps: vec![Step::Empty],
         }
     }
 
-    /// Go to a non-yet visited neighbor
-    pub fn unvisited_neighbor(&self, rng: &mut StdRng, cell: usize) -> Option<Neighbor1> {
+    /// Go to a non-yet visited neighbor, possibly tunneling under a corridor on weave mazes
+    fn unvisited_neighbor(&self, rng: &mut StdRng, cell: usize) -> Option<Step> {
+        let tunnels = self.maze.tunnel_neighbors(cell);
         self.maze
             .neighbors(cell)
             .iter()
-            .filter(|n| self.maze.cell(n.idx).links().is_empty())
+            .filter(|n| self.maze.links(n.idx).is_empty())
+            .map(|n| Step::Link(cell, n))
+            .chain(
+                tunnels
+                    .into_iter()
+                    .filter(|n| self.maze.links(n.idx).is_empty())
+                    .map(|n| Step::Tunnel(cell, n)),
+            )
             .choose(rng)
     }
 }
 
-impl MazeGenerator for RecurBacktrackerGen {
+impl<G: Grid> MazeGenerator<G> for RecurBacktrackerGen<G> {
     fn next(&mut self, rng: &mut StdRng) {
         let next = match self.steps[self.next] {
-            Step::Empty => Step::Walk(0),
+            Step::Empty => match self.maze.first_enabled() {
+                Some(cell) => Step::Walk(cell),
+                None => Step::Finished,
+            },
             Step::Walk(cell) => {
-                if let Some(next) = self.unvisited_neighbor(rng, cell) {
-                    Step::Link(cell, next)
+                if let Some(step) = self.unvisited_neighbor(rng, cell) {
+                    step
                 } else {
                     Step::BackTrack
                 }
@@ -67,6 +80,11 @@ impl MazeGenerator for RecurBacktrackerGen {
                 self.maze.link(cell, next.dir);
                 Step::Walk(next.idx)
             }
+            Step::Tunnel(cell, next) => {
+                self.stack.push(cell);
+                self.maze.tunnel(cell, next.dir);
+                Step::Walk(next.idx)
+            }
         };
 
         if self.next == self.steps.len() - 1 {
@@ -98,6 +116,10 @@ impl MazeGenerator for RecurBacktrackerGen {
                 self.stack.pop();
                 self.maze.unlink(cell, next.dir)
             }
+            Step::Tunnel(cell, next) => {
+                self.stack.pop();
+                self.maze.untunnel(cell, next.dir)
+            }
             Step::Finished => {}
         }
     }
@@ -114,7 +136,7 @@ impl MazeGenerator for RecurBacktrackerGen {
         self.next
     }
 
-    fn maze(&self) -> &Maze {
+    fn maze(&self) -> &G {
         &self.maze
     }
 
@@ -1,6 +1,7 @@
 use super::MazeGenerator;
-use crate::maze::{Maze, Neighbor};
-use rand::prelude::{SliceRandom, StdRng};
+use crate::grid::Grid;
+use crate::maze::{Maze, Neighbor, Neighbor1};
+use rand::prelude::{IteratorRandom, StdRng};
 use rand::Rng;
 
 #[derive(Copy, Clone, Debug)]
@@ -10,40 +11,51 @@ enum Step {
     WalkEast(usize),
     EraseWalk(usize),
     RandWalk(usize),
-    LinkNorth(usize, usize),
+    /// links a cell of the run north, or up
+    LinkNorth(usize, usize, Neighbor),
     Finished,
 }
 
-pub struct SidewinderGen {
-    maze: Maze,
+pub struct SidewinderGen<G = Maze> {
+    maze: G,
     next: usize,
     current_walk: Vec<usize>,
     truncated_walk: Vec<usize>,
     steps: Vec<Step>,
 }
 
-impl SidewinderGen {
-    pub fn new(width: usize, height: usize) -> Self {
+impl<G: Grid> SidewinderGen<G> {
+    pub fn new(maze: G) -> Self {
         Self {
-            maze: Maze::new(width, height),
+            maze,
             next: 0,
             current_walk: vec![],
             truncated_walk: vec![],
             steps: vec![Step::Empty],
         }
     }
+
+    /// Where a run can be closed from: north, or up from the first row of a level
+    fn exit(&self, cell: usize) -> Option<Neighbor1> {
+        self.maze
+            .unwrapped_neighbor_at(cell, Neighbor::North)
+            .or_else(|| self.maze.neighbor_at(cell, Neighbor::Up))
+    }
 }
 
-impl MazeGenerator for SidewinderGen {
+impl<G: Grid> MazeGenerator<G> for SidewinderGen<G> {
     fn next(&mut self, rng: &mut StdRng) {
         let next = match self.steps[self.next] {
             Step::Empty => Step::Direction(0),
             Step::Direction(cell) => {
                 if cell == self.maze.len() {
                     Step::Finished
+                } else if !self.maze.is_enabled(cell) {
+                    // masked cells end the previous run, there is nothing to close
+                    Step::Direction(cell + 1)
                 } else {
-                    let east = self.maze.neighbor_at(cell, Neighbor::East);
-                    let north = self.maze.neighbor_at(cell, Neighbor::North);
+                    let east = self.maze.unwrapped_neighbor_at(cell, Neighbor::East);
+                    let north = self.exit(cell);
                     let finish_walk = match (east, north) {
                         (Some(_), Some(_)) => rng.gen_bool(0.5),
                         (Some(_), None) => false,
@@ -51,11 +63,7 @@ impl MazeGenerator for SidewinderGen {
                         (None, None) => true,
                     };
                     if finish_walk {
-                        if north.is_some() {
-                            Step::RandWalk(cell)
-                        } else {
-                            Step::EraseWalk(cell)
-                        }
+                        Step::RandWalk(cell)
                     } else {
                         Step::WalkEast(cell)
                     }
@@ -74,11 +82,19 @@ impl MazeGenerator for SidewinderGen {
             }
             Step::RandWalk(cell) => {
                 self.current_walk.push(cell);
-                let linked = self.current_walk.choose(rng).unwrap();
-                Step::LinkNorth(cell, *linked)
+                // with masks, not every cell of the run has a northern neighbor
+                let linked = self
+                    .current_walk
+                    .iter()
+                    .filter_map(|c| self.exit(*c).map(|n| (*c, n)))
+                    .choose(rng);
+                match linked {
+                    Some((linked, n)) => Step::LinkNorth(cell, linked, n.dir),
+                    None => Step::EraseWalk(cell),
+                }
             }
-            Step::LinkNorth(cell, linked) => {
-                self.maze.link(linked, Neighbor::North);
+            Step::LinkNorth(cell, linked, dir) => {
+                self.maze.link(linked, dir);
                 Step::EraseWalk(cell)
             }
             Step::Finished => {
@@ -118,8 +134,8 @@ impl MazeGenerator for SidewinderGen {
             Step::RandWalk(cell) => {
                 assert_eq!(cell, self.current_walk.pop().unwrap());
             }
-            Step::LinkNorth(_, linked) => {
-                self.maze.unlink(linked, Neighbor::North);
+            Step::LinkNorth(_, linked, dir) => {
+                self.maze.unlink(linked, dir);
             }
             Step::Finished => {}
         }
@@ -137,7 +153,7 @@ impl MazeGenerator for SidewinderGen {
         self.next
     }
 
-    fn maze(&self) -> &Maze {
+    fn maze(&self) -> &G {
         &self.maze
     }
 
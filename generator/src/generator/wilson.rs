@@ -1,8 +1,8 @@
 use super::MazeGenerator;
 use crate::{
+    grid::Grid,
     maze::Maze,
-    render::{DARK_RED, RED},
-    render_borders, render_cell,
+    render::{RenderGrid, DARK_RED, RED},
 };
 use host_api::{Color, RenderGroup};
 use rand::prelude::{IteratorRandom, SliceRandom, StdRng};
@@ -20,8 +20,8 @@ enum Step {
 }
 
 #[derive(Clone, Eq, PartialEq)]
-pub struct WilsonGen {
-    maze: Maze,
+pub struct WilsonGen<G = Maze> {
+    maze: G,
     unvisited: Vec<usize>,
     visited: Vec<usize>,
     links: Vec<Link>,
@@ -31,10 +31,12 @@ pub struct WilsonGen {
     next: usize,
 }
 
-impl WilsonGen {
-    pub fn new(rng: &mut StdRng, width: usize, height: usize) -> Self {
-        let maze = Maze::new(width, height);
-        let mut unvisited: Vec<_> = (0..maze.len()).into_iter().collect();
+impl<G: Grid> WilsonGen<G> {
+    pub fn new(rng: &mut StdRng, maze: G) -> Self {
+        let mut unvisited: Vec<_> = (0..maze.len())
+            .into_iter()
+            .filter(|c| maze.is_enabled(*c))
+            .collect();
         unvisited.shuffle(rng);
         let current_walk = vec![];
         let steps = vec![Step::Empty];
@@ -49,18 +51,19 @@ impl WilsonGen {
             steps,
         }
     }
+}
 
-    fn render_cell(&self, cell: usize, render_group: &mut RenderGroup, color: Color) {
-        let x = cell % self.maze.width();
-        let y = cell / self.maze.width();
-        render_cell(render_group, x, y, color);
+impl<G: RenderGrid> WilsonGen<G> {
+    fn render_cell(&self, cell: usize, render_group: &mut RenderGroup, level: usize, color: Color) {
+        self.maze.render_cell(render_group, level, cell, color);
     }
 
-    fn render_visited(&self, render_group: &mut RenderGroup) {
+    fn render_visited(&self, render_group: &mut RenderGroup, level: usize) {
         for cell in &self.visited {
             self.render_cell(
                 *cell,
                 render_group,
+                level,
                 Color {
                     r: 34,
                     g: 70,
@@ -70,17 +73,18 @@ impl WilsonGen {
         }
     }
 
-    fn render_unvisited(&self, render_group: &mut RenderGroup) {
+    fn render_unvisited(&self, render_group: &mut RenderGroup, level: usize) {
         for cell in &self.unvisited {
-            self.render_cell(*cell, render_group, Color { r: 31, g: 0, b: 0 })
+            self.render_cell(*cell, render_group, level, Color { r: 31, g: 0, b: 0 })
         }
     }
 
-    fn render_current_walk(&self, render_group: &mut RenderGroup) {
+    fn render_current_walk(&self, render_group: &mut RenderGroup, level: usize) {
         for cell in &self.current_walk {
             self.render_cell(
                 *cell,
                 render_group,
+                level,
                 Color {
                     r: 120,
                     g: 80,
@@ -91,31 +95,34 @@ impl WilsonGen {
     }
 }
 
-impl MazeGenerator for WilsonGen {
-    fn render(&mut self, render_group: &mut RenderGroup, border_color: Color) {
+impl<G: Grid> MazeGenerator<G> for WilsonGen<G> {
+    fn render(&mut self, render_group: &mut RenderGroup, border_color: Color, level: usize)
+    where
+        G: RenderGrid,
+    {
         match self.steps[self.next] {
             Step::Empty => {}
             Step::StartWalk => {
-                self.render_visited(render_group);
-                self.render_unvisited(render_group);
+                self.render_visited(render_group, level);
+                self.render_unvisited(render_group, level);
             }
             Step::RandDir(cell) => {
-                self.render_visited(render_group);
-                self.render_unvisited(render_group);
-                self.render_current_walk(render_group);
-                self.render_cell(cell, render_group, DARK_RED)
+                self.render_visited(render_group, level);
+                self.render_unvisited(render_group, level);
+                self.render_current_walk(render_group, level);
+                self.render_cell(cell, render_group, level, DARK_RED)
             }
             Step::Walk(cell, dir) => {
-                self.render_visited(render_group);
-                self.render_unvisited(render_group);
-                self.render_current_walk(render_group);
-                self.render_cell(cell, render_group, DARK_RED);
-                self.render_cell(dir, render_group, RED);
+                self.render_visited(render_group, level);
+                self.render_unvisited(render_group, level);
+                self.render_current_walk(render_group, level);
+                self.render_cell(cell, render_group, level, DARK_RED);
+                self.render_cell(dir, render_group, level, RED);
             }
             Step::EraseWalk(cell) => {
-                self.render_visited(render_group);
-                self.render_unvisited(render_group);
-                self.render_current_walk(render_group);
+                self.render_visited(render_group, level);
+                self.render_unvisited(render_group, level);
+                self.render_current_walk(render_group, level);
                 let idx = self
                     .current_walk
                     .iter()
@@ -125,6 +132,7 @@ impl MazeGenerator for WilsonGen {
                     self.render_cell(
                         *idx,
                         render_group,
+                        level,
                         Color {
                             r: 210,
                             g: 130,
@@ -134,12 +142,13 @@ impl MazeGenerator for WilsonGen {
                 }
             }
             Step::FinishWalk(cell, _) => {
-                self.render_visited(render_group);
-                self.render_unvisited(render_group);
+                self.render_visited(render_group, level);
+                self.render_unvisited(render_group, level);
                 for idx in &self.current_walk {
                     self.render_cell(
                         *idx,
                         render_group,
+                        level,
                         Color {
                             r: 34,
                             g: 110,
@@ -150,6 +159,7 @@ impl MazeGenerator for WilsonGen {
                 self.render_cell(
                     cell,
                     render_group,
+                    level,
                     Color {
                         r: 34,
                         g: 110,
@@ -158,12 +168,13 @@ impl MazeGenerator for WilsonGen {
                 );
             }
             Step::Link(cell, _) => {
-                self.render_visited(render_group);
-                self.render_unvisited(render_group);
+                self.render_visited(render_group, level);
+                self.render_unvisited(render_group, level);
                 for idx in &self.current_walk {
                     self.render_cell(
                         *idx,
                         render_group,
+                        level,
                         Color {
                             r: 34,
                             g: 110,
@@ -174,6 +185,7 @@ impl MazeGenerator for WilsonGen {
                 self.render_cell(
                     cell,
                     render_group,
+                    level,
                     Color {
                         r: 34,
                         g: 110,
@@ -183,10 +195,9 @@ impl MazeGenerator for WilsonGen {
             }
             Step::Finished => {}
         }
-        for y in 0..self.maze.height() {
-            for x in 0..self.maze.width() {
-                render_borders(render_group, x, y, &self.maze, border_color);
-            }
+        for cell in 0..self.maze.len() {
+            self.maze
+                .render_borders(render_group, level, cell, border_color);
         }
     }
 
@@ -320,7 +331,7 @@ impl MazeGenerator for WilsonGen {
         self.steps.len()
     }
 
-    fn maze(&self) -> &Maze {
+    fn maze(&self) -> &G {
         &self.maze
     }
 
@@ -0,0 +1,192 @@
+use crate::grid::Grid;
+use crate::maze::Maze;
+use std::fmt::Write;
+
+/// Graph file formats
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GraphFormat {
+    /// graphviz
+    Dot,
+    GraphMl,
+}
+
+/// A maze as a graph, for graph tools: enabled cells are nodes and links are edges. Nodes have
+/// the position of their cell, with rings as rows on polar mazes
+pub struct Graph<'a> {
+    maze: &'a Maze,
+    distances: Option<&'a [usize]>,
+}
+
+impl<'a> Graph<'a> {
+    pub fn new(maze: &'a Maze) -> Self {
+        Self {
+            maze,
+            distances: None,
+        }
+    }
+
+    /// Nodes get their distance, and edges are weighted with the distance of their furthest
+    /// end. See `dijkstra::flood`, unreachable cells have no distance
+    pub fn distances(mut self, distances: &'a [usize]) -> Self {
+        self.distances = Some(distances);
+        self
+    }
+
+    pub fn write(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Dot => self.dot(),
+            GraphFormat::GraphMl => self.graphml(),
+        }
+    }
+
+    fn nodes(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.maze.len()).filter(move |c| self.maze.is_enabled(*c))
+    }
+
+    /// every link once, from the lowest cell
+    fn edges(&self) -> Vec<(usize, usize)> {
+        let mut edges = vec![];
+        for cell in self.nodes() {
+            for n in self.maze.linked_neighbors(cell).iter() {
+                if cell < n.idx {
+                    edges.push((cell, n.idx));
+                }
+            }
+        }
+        edges
+    }
+
+    fn distance(&self, cell: usize) -> Option<usize> {
+        self.distances
+            .map(|d| d[cell])
+            .filter(|d| *d < self.maze.len())
+    }
+
+    fn weight(&self, (a, b): (usize, usize)) -> Option<usize> {
+        Some(self.distance(a)?.max(self.distance(b)?))
+    }
+
+    fn dot(&self) -> String {
+        let maze = self.maze;
+        let mut dot = String::from("graph maze {\n");
+        for cell in self.nodes() {
+            let (x, y) = maze.position(cell);
+            let _ = write!(
+                dot,
+                "  {} [x={}, y={}, level={}",
+                cell,
+                x,
+                y,
+                maze.level(cell)
+            );
+            if let Some(distance) = self.distance(cell) {
+                let _ = write!(dot, ", distance={}", distance);
+            }
+            dot.push_str("];\n");
+        }
+        for edge in self.edges() {
+            let _ = write!(dot, "  {} -- {}", edge.0, edge.1);
+            if let Some(weight) = self.weight(edge) {
+                let _ = write!(dot, " [weight={}]", weight);
+            }
+            dot.push_str(";\n");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn graphml(&self) -> String {
+        let maze = self.maze;
+        let mut xml = String::from(concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            "\n",
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#,
+            "\n"
+        ));
+        let keys = [
+            ("x", "node"),
+            ("y", "node"),
+            ("level", "node"),
+            ("distance", "node"),
+            ("weight", "edge"),
+        ];
+        for (key, target) in keys.iter() {
+            let _ = writeln!(
+                xml,
+                r#"  <key id="{0}" for="{1}" attr.name="{0}" attr.type="int"/>"#,
+                key, target
+            );
+        }
+        xml.push_str("  <graph id=\"maze\" edgedefault=\"undirected\">\n");
+        for cell in self.nodes() {
+            let (x, y) = maze.position(cell);
+            let _ = writeln!(xml, r#"    <node id="n{}">"#, cell);
+            let _ = writeln!(xml, r#"      <data key="x">{}</data>"#, x);
+            let _ = writeln!(xml, r#"      <data key="y">{}</data>"#, y);
+            let _ = writeln!(
+                xml,
+                r#"      <data key="level">{}</data>"#,
+                maze.level(cell)
+            );
+            if let Some(distance) = self.distance(cell) {
+                let _ = writeln!(xml, r#"      <data key="distance">{}</data>"#, distance);
+            }
+            xml.push_str("    </node>\n");
+        }
+        for edge in self.edges() {
+            let _ = write!(
+                xml,
+                r#"    <edge source="n{}" target="n{}""#,
+                edge.0, edge.1
+            );
+            match self.weight(edge) {
+                Some(weight) => {
+                    let _ = writeln!(xml, ">");
+                    let _ = writeln!(xml, r#"      <data key="weight">{}</data>"#, weight);
+                    xml.push_str("    </edge>\n");
+                }
+                None => xml.push_str("/>\n"),
+            }
+        }
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra;
+    use crate::maze::{Mask, Neighbor};
+
+    #[test]
+    fn graphs() {
+        let mut mask = Mask::new(2, 2);
+        mask.disable(3);
+        let mut maze = Maze::with_mask(mask);
+        maze.link(0, Neighbor::East);
+        maze.link(0, Neighbor::South);
+        assert_eq!(
+            Graph::new(&maze).write(GraphFormat::Dot),
+            "graph maze {\n\
+            \x20 0 [x=0, y=0, level=0];\n\
+            \x20 1 [x=1, y=0, level=0];\n\
+            \x20 2 [x=0, y=1, level=0];\n\
+            \x20 0 -- 2;\n\
+            \x20 0 -- 1;\n\
+             }\n"
+        );
+
+        let distances = dijkstra::flood(1, &maze);
+        let graph = Graph::new(&maze).distances(&distances);
+        let dot = graph.write(GraphFormat::Dot);
+        assert!(dot.contains("  2 [x=0, y=1, level=0, distance=2];\n"));
+        assert!(dot.contains("  0 -- 2 [weight=2];\n"));
+        let xml = graph.write(GraphFormat::GraphMl);
+        assert_eq!(xml.matches("<node ").count(), 3);
+        assert_eq!(xml.matches("<edge ").count(), 2);
+        assert!(xml.contains(
+            "    <edge source=\"n0\" target=\"n1\">\n      <data key=\"weight\">1</data>\n"
+        ));
+    }
+}
@@ -0,0 +1,62 @@
+use crate::maze::{Links, Neighbor, Neighbor1, Neighbors};
+
+/// What generators and solvers need from a grid, whatever the shape of its cells.
+/// Cells are indices in `0..len()`, and neighbors are reached through a `Neighbor` direction
+pub trait Grid {
+    /// number of cells, including the masked ones
+    fn len(&self) -> usize;
+
+    /// masked cells are never linked
+    fn is_enabled(&self, cell: usize) -> bool;
+
+    fn first_enabled(&self) -> Option<usize> {
+        (0..self.len()).find(|c| self.is_enabled(*c))
+    }
+
+    /// the enabled cell closest to the middle of the grid
+    fn middle_cell(&self) -> usize;
+
+    /// column and row of a cell, for polar grids its position in the ring and the ring
+    fn position(&self, cell: usize) -> (usize, usize);
+
+    fn links(&self, cell: usize) -> Links;
+
+    /// neighbor in the given direction, unless it is outside the grid or masked
+    fn neighbor_at(&self, cell: usize, dir: Neighbor) -> Option<Neighbor1>;
+
+    /// like `neighbor_at`, but never wrapping around the edges
+    fn unwrapped_neighbor_at(&self, cell: usize, dir: Neighbor) -> Option<Neighbor1> {
+        self.neighbor_at(cell, dir)
+    }
+
+    fn neighbors(&self, cell: usize) -> Neighbors;
+
+    /// direction from `a` to its neighbor `b`
+    fn from_a_to_b(&self, a: usize, b: usize) -> Option<Neighbor> {
+        self.neighbors(a).iter().find(|n| n.idx == b).map(|n| n.dir)
+    }
+
+    fn link(&mut self, cell: usize, dir: Neighbor);
+    fn unlink(&mut self, cell: usize, dir: Neighbor);
+
+    fn linked_neighbors(&self, cell: usize) -> Neighbors;
+
+    /// Cells that can be reached by tunneling under another one. Grids without tunnels keep
+    /// the defaults
+    fn tunnel_neighbors(&self, _cell: usize) -> Vec<Neighbor1> {
+        vec![]
+    }
+
+    fn tunnel(&mut self, _cell: usize, _dir: Neighbor) {
+        panic!("this grid has no tunnels")
+    }
+
+    fn untunnel(&mut self, _cell: usize, _dir: Neighbor) {
+        panic!("this grid has no tunnels")
+    }
+
+    /// whether the cell is a tunnel under another one, rather than a place of its own
+    fn is_under(&self, _cell: usize) -> bool {
+        false
+    }
+}
@@ -3,15 +3,25 @@
 
 use dbg::debug_reload_maze;
 use generator::*;
+use graph::GraphFormat;
+use grid::Grid;
 use host_api::{Color, HostApi, Input, RenderCommand};
+use maze::{JsonFormat, Maze, Topology, Transform, Wrap};
 use rand::{prelude::StdRng, SeedableRng};
-use render::{render_borders, render_cell};
+use render::{render_overlay, RenderGrid};
+use stats::Stats;
 
 mod dbg;
 mod dijkstra;
 mod generator;
+mod graph;
+mod grid;
 mod maze;
+mod raster;
 mod render;
+mod save;
+mod stats;
+mod svg;
 
 #[no_mangle]
 pub extern "C" fn init(_host_api: &mut dyn HostApi) -> *mut GameState {
@@ -19,7 +29,8 @@ pub extern "C" fn init(_host_api: &mut dyn HostApi) -> *mut GameState {
     let maze_width = 15;
     let maze_height = 15;
     let generator = Generator::HuntAndKill;
-    let rng = StdRng::seed_from_u64(1234);
+    let seed = 1234;
+    let rng = StdRng::seed_from_u64(seed);
     let debug = Debug {
         debug_borders_color: [117, 140, 140],
         debug_autoplay: false,
@@ -28,13 +39,31 @@ pub extern "C" fn init(_host_api: &mut dyn HostApi) -> *mut GameState {
         debug_step: 0,
         debug_maze_width: maze_width,
         debug_maze_height: maze_height,
+        debug_mask_path: String::new(),
+        debug_topology: Topology::Square,
+        debug_maze_levels: 1,
+        debug_level: 0,
+        debug_wrap: Wrap::None,
+        debug_braid: 0,
+        debug_transform: None,
+        debug_rooms: 0,
+        debug_doors: 2,
+        debug_validate: false,
+        debug_save_path: String::new(),
+        debug_save_format: SaveFormat::Binary,
+        debug_png_cell_size: 20.0,
+        debug_load_path: String::new(),
     };
     let distances = vec![];
     let longest_path = vec![];
 
     let game = GameState {
-        wilson: MazeGen::HuntAndKill(Box::new(HuntAndKillGen::new(maze_width, maze_height))),
+        wilson: MazeGen::HuntAndKill(Box::new(HuntAndKillGen::new(Maze::new(
+            maze_width,
+            maze_height,
+        )))),
         rng,
+        seed,
         debug,
         maze_width,
         maze_height,
@@ -45,6 +74,11 @@ pub extern "C" fn init(_host_api: &mut dyn HostApi) -> *mut GameState {
         camera_x: 0.0,
         camera_y: 0.0,
         overlay: None,
+        braided: None,
+        braid: 0,
+        transform: None,
+        stats: None,
+        loaded: None,
     };
     Box::into_raw(Box::new(game))
 }
@@ -53,11 +87,18 @@ pub extern "C" fn init(_host_api: &mut dyn HostApi) -> *mut GameState {
 pub extern "C" fn update(state: &mut GameState, host_api: &mut dyn HostApi, input: &Input) -> bool {
     debug_reload_maze(state, input);
     let wilson = state.wilson.maze_mut();
-    if wilson.completed() && state.distances.is_empty() {
-        state.distances = dijkstra::flood(wilson.maze().middle_cell(), &wilson.maze());
+    let maze = state.braided.as_ref().unwrap_or_else(|| wilson.maze());
+    let completed = wilson.completed() || state.loaded.is_some();
+    if completed && state.distances.is_empty() {
+        state.distances = dijkstra::flood(maze.middle_cell(), maze);
     }
-    if wilson.completed() && state.longest_path.is_empty() {
-        state.longest_path = dijkstra::longest_path(wilson.maze());
+    if completed && state.longest_path.is_empty() {
+        state.longest_path = dijkstra::longest_path(maze);
+    }
+    if completed && state.stats.is_none() {
+        let mut stats = Stats::new(maze, &state.longest_path);
+        stats.hash = Some(maze.canonical_hash());
+        state.stats = Some(stats);
     }
     if input.mouse_wheel_up {
         state.camera_zoom /= 1.1;
@@ -88,37 +129,34 @@ pub extern "C" fn update(state: &mut GameState, host_api: &mut dyn HostApi, inpu
         b: state.debug.debug_borders_color[2],
     };
 
-    let maze = wilson.maze();
-    match state.overlay {
-        Some(Overlay::Distances) => {
-            let max_distance = state.distances.iter().max().cloned().unwrap() as f64;
-            for (cell, distance) in state.distances.iter().cloned().enumerate() {
-                let x = cell % maze.width();
-                let y = cell / maze.width();
-                render_cell(
+    let maze = state.braided.as_ref().unwrap_or_else(|| wilson.maze());
+    if let Some(overlay) = state.overlay {
+        render_overlay(
+            host_api.render_group(),
+            maze,
+            state.debug.debug_level,
+            overlay,
+            &state.distances,
+            &state.longest_path,
+        );
+    }
+    match &state.braided {
+        Some(maze) => {
+            for cell in 0..maze.len() {
+                maze.render_borders(
                     host_api.render_group(),
-                    x,
-                    y,
-                    Color::gradient_gray(distance as f64 / max_distance),
+                    state.debug.debug_level,
+                    cell,
+                    border_color,
                 );
-                // let text = format!("d:{}", state.distances[idx]);
-                // render_cell_text(host_api.render_group(), 0.0, 0.0, text);
-
-                // if idx == (state.generation.current_step + 1) / 2 {
-                //     render_cell(host_api.render_group(), x, y, RED)
-                // }
-            }
-        }
-        Some(Overlay::LongestPath) => {
-            for cell in &state.longest_path {
-                let x = cell % maze.width();
-                let y = cell / maze.width();
-                render_cell(host_api.render_group(), x, y, Color::gradient_gray(0.0));
             }
         }
-        None => {}
+        None => wilson.render(
+            host_api.render_group(),
+            border_color,
+            state.debug.debug_level,
+        ),
     }
-    wilson.render(host_api.render_group(), border_color);
 
     let needs_update = true;
     needs_update
@@ -133,6 +171,27 @@ pub struct Debug {
     debug_autoplay: bool,
     debug_maze_width: usize,
     debug_maze_height: usize,
+    debug_mask_path: String,
+    debug_topology: Topology,
+    debug_maze_levels: usize,
+    /// level being rendered
+    debug_level: usize,
+    debug_wrap: Wrap,
+    /// percentage of dead ends to remove once the maze is generated
+    debug_braid: usize,
+    /// rotates or mirrors the generated maze
+    debug_transform: Option<Transform>,
+    /// rooms placed at random before generating the maze
+    debug_rooms: usize,
+    /// doors of every room
+    debug_doors: usize,
+    /// validate the maze after each move through the steps, only offered in debug builds
+    debug_validate: bool,
+    debug_save_path: String,
+    debug_save_format: SaveFormat,
+    /// pixels per tile of saved images
+    debug_png_cell_size: f32,
+    debug_load_path: String,
 }
 
 #[repr(C)]
@@ -145,10 +204,37 @@ pub struct GameState {
     generator: Generator,
     overlay: Option<Overlay>,
     rng: StdRng,
+    /// the rng is seeded with it whenever a new maze is generated
+    seed: u64,
     camera_zoom: f32,
     camera_x: f32,
     camera_y: f32,
     wilson: MazeGen,
+    /// the generated maze after opening its rooms, braiding and transforming, the generator keeps
+    /// its own to replay the steps
+    braided: Option<Maze>,
+    /// percentage of dead ends removed from `braided`
+    braid: usize,
+    /// applied to `braided`
+    transform: Option<Transform>,
+    stats: Option<Stats>,
+    /// maze read from a file, shown instead of the generated one until the next restart
+    loaded: Option<Maze>,
+}
+
+/// How the debug window saves mazes
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SaveFormat {
+    /// see `SavedMaze`
+    Binary,
+    /// the maze and its solution
+    Json(JsonFormat),
+    /// a drawing of the current level, with the overlay
+    Svg,
+    /// like `Svg`, as an image
+    Png,
+    /// cells and their links, weighted by the distances
+    Graph(GraphFormat),
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
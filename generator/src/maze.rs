@@ -1,135 +1,430 @@
-#[derive(Clone, Eq, PartialEq)]
+mod ascii;
+mod binary;
+mod braid;
+mod hash;
+mod hex;
+mod json;
+mod level;
+mod mask;
+mod polar;
+mod rooms;
+mod scan;
+mod transform;
+mod triangle;
+mod validate;
+mod weave;
+mod wrap;
+
+pub use self::ascii::AsciiError;
+pub use self::hash::dedup;
+pub use self::json::{JsonFormat, MazeDocument, Passages};
+pub use self::mask::{Mask, MaskError};
+pub use self::polar::PolarGrid;
+pub use self::rooms::Room;
+pub use self::scan::ScanError;
+pub use self::transform::Transform;
+pub use self::validate::{Report, Violation};
+pub use self::wrap::Wrap;
+
+use crate::grid::Grid;
+use serde::{Deserialize, Serialize};
+
+/// most neighbors a cell can have, for any topology
+pub const MAX_NEIGHBORS: usize = 8;
+
+/// Serializes as a JSON document, see `MazeDocument`
+#[derive(Clone, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[serde(into = "MazeDocument", try_from = "MazeDocument")]
 pub struct Maze {
     cells: Vec<Cell>,
     width: usize,
+    mask: Mask,
+    topology: Topology,
+    polar: Option<PolarGrid>,
+    /// levels stacked on top of each other, all of them shaped by the mask
+    levels: usize,
+    wrap: Wrap,
+    /// cells under which a tunnel passes, for every under cell. Under cells come after the rest
+    unders: Vec<usize>,
+    /// rooms left out of the generation, see `Maze::with_rooms`
+    rooms: Vec<Room>,
+    /// one bit per cell of the grid inside a room, empty without rooms
+    in_rooms: Vec<u64>,
+}
+
+/// Shape of the cells and how they are tiled
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum Topology {
+    Square,
+    /// flat topped hexagons, odd columns are shifted half a cell down
+    Hex,
+    /// concentric rings, see `PolarGrid`
+    Polar,
+    /// triangles pointing up and down, see `Maze::is_upright`
+    Triangle,
+    /// square cells, where passages can tunnel under perpendicular corridors
+    Weave,
+}
+
+impl Topology {
+    /// directions a cell can have neighbors at
+    pub fn directions(self) -> &'static [Neighbor] {
+        match self {
+            Topology::Square | Topology::Triangle | Topology::Weave => &[
+                Neighbor::North,
+                Neighbor::South,
+                Neighbor::East,
+                Neighbor::West,
+            ],
+            Topology::Hex => &[
+                Neighbor::North,
+                Neighbor::South,
+                Neighbor::NorthEast,
+                Neighbor::NorthWest,
+                Neighbor::SouthEast,
+                Neighbor::SouthWest,
+            ],
+            Topology::Polar => &[
+                Neighbor::Inward,
+                Neighbor::Outward,
+                Neighbor::OutwardClockwise,
+                Neighbor::Clockwise,
+                Neighbor::CounterClockwise,
+            ],
+        }
+    }
 }
 
 impl Maze {
     pub fn new(width: usize, height: usize) -> Self {
-        let cells = (0..(width * height))
-            .into_iter()
-            .map(|_| Cell::default())
-            .collect();
-        Self { cells, width }
+        Self::with_mask(Mask::new(width, height))
+    }
+
+    pub fn with_mask(mask: Mask) -> Self {
+        Self::with_topology(Topology::Square, mask)
+    }
+
+    /// grids made of rows and columns, polar mazes are built with `Maze::polar`
+    pub fn with_topology(topology: Topology, mask: Mask) -> Self {
+        Self::with_levels(topology, mask, 1)
+    }
+
+    /// several grids stacked on top of each other, linked with `Neighbor::Up` and `Neighbor::Down`
+    pub fn with_levels(topology: Topology, mask: Mask, levels: usize) -> Self {
+        assert_ne!(topology, Topology::Polar, "use Maze::polar instead");
+        assert!(levels > 0, "a maze needs at least one level");
+        assert!(
+            Self::connects(topology, &mask),
+            "the mask splits the {:?} grid in pieces",
+            topology
+        );
+        Self::unchecked(topology, mask, levels)
+    }
+
+    /// Whether the enabled cells of the mask reach each other on that grid. Masks only check
+    /// the sides of square cells, triangles have fewer neighbors
+    pub fn connects(topology: Topology, mask: &Mask) -> bool {
+        Self::unchecked(topology, mask.clone(), 1).is_connected()
+    }
+
+    fn unchecked(topology: Topology, mask: Mask, levels: usize) -> Self {
+        let cells = vec![Cell::default(); mask.len() * levels];
+        Self {
+            cells,
+            width: mask.width(),
+            mask,
+            topology,
+            polar: None,
+            levels,
+            wrap: Wrap::None,
+            unders: vec![],
+            rooms: vec![],
+            in_rooms: vec![],
+        }
+    }
+
+    pub fn polar(rings: usize) -> Self {
+        assert!(rings > 0, "a polar maze needs at least one ring");
+        let grid = PolarGrid::new(rings);
+        let cells = vec![Cell::default(); grid.len()];
+        Self {
+            cells,
+            width: grid.ring_len(rings - 1),
+            mask: Mask::new(grid.len(), 1),
+            topology: Topology::Polar,
+            polar: Some(grid),
+            levels: 1,
+            wrap: Wrap::None,
+            unders: vec![],
+            rooms: vec![],
+            in_rooms: vec![],
+        }
+    }
+
+    /// Floods the enabled cells from the first one through the neighbors of the grid,
+    /// generators only finish when it reaches all of them. Under cells are only tunnels
+    fn is_connected(&self) -> bool {
+        let first = match self.first_enabled() {
+            Some(cell) => cell,
+            None => return true,
+        };
+        let mut reached = vec![false; self.len()];
+        reached[first] = true;
+        let mut pending = vec![first];
+        while let Some(cell) = pending.pop() {
+            for n in self.neighbors(cell).iter() {
+                if !reached[n.idx] {
+                    reached[n.idx] = true;
+                    pending.push(n.idx);
+                }
+            }
+        }
+        (0..self.grid_len()).all(|c| reached[c] || !self.is_enabled(c))
+    }
+
+    pub fn polar_grid(&self) -> Option<&PolarGrid> {
+        self.polar.as_ref()
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
     }
 
     pub fn cell(&self, pos: usize) -> &Cell {
         &self.cells[pos]
     }
 
-    pub fn middle_cell(&self) -> usize {
-        (self.height() / 2) * self.width + self.width / 2
+    /// cells on every level, without the under cells
+    fn grid_len(&self) -> usize {
+        self.mask.len() * self.levels
     }
 
-    pub fn cells(&self) -> &[Cell] {
-        &self.cells
+    /// directions a cell can have neighbors at, including other levels
+    pub fn directions(&self) -> impl Iterator<Item = Neighbor> {
+        let levels: &[Neighbor] = if self.levels > 1 {
+            &[Neighbor::Up, Neighbor::Down]
+        } else {
+            &[]
+        };
+        self.topology
+            .directions()
+            .iter()
+            .chain(levels.iter())
+            .cloned()
     }
 
-    pub fn len(&self) -> usize {
-        self.cells.len()
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
     }
 
-    pub fn neighbor_at(&self, cell: usize, n: Neighbor) -> Option<Neighbor1> {
+    fn square_neighbor_at(&self, cell: usize, n: Neighbor) -> Option<usize> {
         match n {
             Neighbor::North => {
                 // reject cells on the first row
                 if cell < self.width {
-                    None
+                    if self.wraps_north_south() {
+                        Some(cell + self.mask.len() - self.width)
+                    } else {
+                        None
+                    }
                 } else {
-                    Some(Neighbor1 {
-                        dir: n,
-                        idx: cell - self.width,
-                    })
+                    Some(cell - self.width)
                 }
             }
             Neighbor::South => {
                 // reject cells on the last row
                 let c = cell + self.width;
-                if c >= self.cells.len() {
-                    None
+                if c >= self.mask.len() {
+                    if self.wraps_north_south() {
+                        Some(c - self.mask.len())
+                    } else {
+                        None
+                    }
                 } else {
-                    Some(Neighbor1 { dir: n, idx: c })
+                    Some(c)
                 }
             }
             Neighbor::East => {
                 // reject cells on the last column
                 if (cell + 1) % self.width == 0 {
-                    None
+                    if self.wraps_east_west() {
+                        Some(cell + 1 - self.width)
+                    } else {
+                        None
+                    }
                 } else {
-                    Some(Neighbor1 {
-                        dir: n,
-                        idx: cell + 1,
-                    })
+                    Some(cell + 1)
                 }
             }
             Neighbor::West => {
                 // reject cells on the first column
                 if cell % self.width == 0 {
-                    None
+                    if self.wraps_east_west() {
+                        Some(cell + self.width - 1)
+                    } else {
+                        None
+                    }
                 } else {
-                    Some(Neighbor1 {
-                        dir: n,
-                        idx: cell - 1,
-                    })
+                    Some(cell - 1)
                 }
             }
+            _ => None,
         }
     }
-    pub fn unlink(&mut self, cell: usize, n: Neighbor) {
-        let neighbor = self.neighbor_at(cell, n).unwrap();
-        self.cells[cell].unlink(n);
-        self.cells[neighbor.idx].unlink(n.opposite());
-    }
-
-    pub fn from_a_to_b(&self, a: usize, b: usize) -> Option<Neighbor> {
-        let dir = if a + self.width == b {
-            Neighbor::South
-        } else if a + 1 == b {
-            Neighbor::East
-        } else if b + 1 == a {
-            Neighbor::West
-        } else if b + self.width == a {
-            Neighbor::North
+
+    /// direction that leads from `neighbor` back to `cell`
+    fn back(&self, cell: usize, neighbor: Neighbor1) -> Neighbor {
+        match neighbor.dir {
+            // on polar grids, an inward cell can have two outward neighbors
+            Neighbor::Inward => self.polar_outward_dir(self.level_cell(cell)),
+            dir => dir.opposite(),
+        }
+    }
+
+    fn linked_neighbor_at(&self, cell: usize, dir: Neighbor) -> Option<Neighbor1> {
+        if !self.cell(cell).has_link(dir) {
+            return None;
+        }
+        let n = self.neighbor_at(cell, dir)?;
+        let back = self.back(cell, n);
+        if self.cell(n.idx).has_link(back) {
+            Some(n)
         } else {
+            // on weave mazes, the link may go through a tunnel under the neighbor
+            self.under_cell(n.idx)
+                .filter(|under| self.cell(*under).has_link(back))
+                .map(|idx| Neighbor1 { dir, idx })
+        }
+    }
+
+    /// rows of a single level, or rings of a polar maze
+    pub fn height(&self) -> usize {
+        match &self.polar {
+            Some(grid) => grid.rings(),
+            None => self.mask.len() / self.width,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+}
+
+impl Grid for Maze {
+    fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn is_enabled(&self, cell: usize) -> bool {
+        // under cells are never masked
+        cell >= self.grid_len()
+            || (self.mask.is_enabled(cell % self.mask.len()) && !self.in_room(cell))
+    }
+
+    fn middle_cell(&self) -> usize {
+        if self.topology == Topology::Polar {
+            return 0;
+        }
+        let (mid_x, mid_y) = (self.width / 2, self.height() / 2);
+        (0..self.mask.len())
+            .filter(|c| self.is_enabled(*c))
+            .min_by_key(|c| {
+                let (x, y) = self.position(*c);
+                (x as isize - mid_x as isize).abs() + (y as isize - mid_y as isize).abs()
+            })
+            .unwrap_or(0)
+    }
+
+    fn position(&self, cell: usize) -> (usize, usize) {
+        let cell = self.level_cell(cell);
+        match &self.polar {
+            Some(grid) => {
+                let (ring, pos) = grid.locate(cell);
+                (pos, ring)
+            }
+            None => (cell % self.width, cell / self.width),
+        }
+    }
+
+    fn links(&self, cell: usize) -> Links {
+        self.cells[cell].links()
+    }
+
+    fn neighbor_at(&self, cell: usize, n: Neighbor) -> Option<Neighbor1> {
+        if !self.is_enabled(cell) {
             return None;
+        }
+        let idx = if let Some(over) = self.over_cell(cell) {
+            self.under_neighbor_at(cell, over, n)
+        } else if n == Neighbor::Up || n == Neighbor::Down {
+            self.level_neighbor_at(cell, n)
+        } else {
+            // topologies only know about the first level, other ones are laid out the same way
+            let level_cell = self.level_cell(cell);
+            let idx = match self.topology {
+                Topology::Square | Topology::Weave => self.square_neighbor_at(level_cell, n),
+                Topology::Hex => self.hex_neighbor_at(level_cell, n),
+                Topology::Polar => self.polar_neighbor_at(level_cell, n),
+                Topology::Triangle => self.triangle_neighbor_at(level_cell, n),
+            };
+            idx.map(|idx| cell - level_cell + idx)
         };
-        Some(dir)
+        idx.filter(|idx| self.is_enabled(*idx))
+            .map(|idx| Neighbor1 { dir: n, idx })
+    }
+
+    fn unwrapped_neighbor_at(&self, cell: usize, dir: Neighbor) -> Option<Neighbor1> {
+        if self.is_wrapped(cell, dir) {
+            None
+        } else {
+            self.neighbor_at(cell, dir)
+        }
     }
 
-    pub fn link(&mut self, cell: usize, n: Neighbor) {
+    fn neighbors(&self, cell: usize) -> Neighbors {
+        let mut inner = [None; MAX_NEIGHBORS];
+        for (n, dir) in inner.iter_mut().zip(self.directions()) {
+            *n = self.neighbor_at(cell, dir);
+        }
+        Neighbors { inner }
+    }
+
+    fn link(&mut self, cell: usize, n: Neighbor) {
         let neighbor = self.neighbor_at(cell, n).unwrap();
+        let back = self.back(cell, neighbor);
         self.cells[cell].link(n);
-        self.cells[neighbor.idx].link(n.opposite());
+        self.cells[neighbor.idx].link(back);
     }
 
-    pub fn linked_neighbors<'a>(&'a self, cell: usize) -> Vec<Neighbor1> {
-        Neighbor::iter()
-            .iter()
-            .cloned()
-            .filter_map(|dir| self.neighbor_at(cell, dir))
-            .filter(|n| {
-                self.cell(cell).has_link(n.dir) && self.cell(n.idx).has_link(n.dir.opposite())
-            })
-            .collect()
+    fn unlink(&mut self, cell: usize, n: Neighbor) {
+        let neighbor = self.neighbor_at(cell, n).unwrap();
+        let back = self.back(cell, neighbor);
+        self.cells[cell].unlink(n);
+        self.cells[neighbor.idx].unlink(back);
     }
 
-    pub fn neighbors(&self, cell: usize) -> Neighbors {
-        Neighbors {
-            inner: [
-                self.neighbor_at(cell, Neighbor::North),
-                self.neighbor_at(cell, Neighbor::South),
-                self.neighbor_at(cell, Neighbor::East),
-                self.neighbor_at(cell, Neighbor::West),
-            ],
+    fn linked_neighbors(&self, cell: usize) -> Neighbors {
+        let mut inner = [None; MAX_NEIGHBORS];
+        for (n, dir) in inner.iter_mut().zip(self.directions()) {
+            *n = self.linked_neighbor_at(cell, dir);
         }
+        Neighbors { inner }
     }
 
-    pub fn height(&self) -> usize {
-        self.cells.len() / self.width
+    fn tunnel_neighbors(&self, cell: usize) -> Vec<Neighbor1> {
+        self.weave_tunnel_neighbors(cell)
     }
 
-    pub fn width(&self) -> usize {
-        self.width
+    fn tunnel(&mut self, cell: usize, dir: Neighbor) {
+        self.weave_tunnel(cell, dir)
+    }
+
+    fn untunnel(&mut self, cell: usize, dir: Neighbor) {
+        self.weave_untunnel(cell, dir)
+    }
+
+    fn is_under(&self, cell: usize) -> bool {
+        self.over_cell(cell).is_some()
     }
 }
 
@@ -139,35 +434,67 @@ pub struct Neighbor1 {
     pub idx: usize,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Neighbor {
     North,
     South,
     East,
     West,
+    NorthEast,
+    NorthWest,
+    SouthEast,
+    SouthWest,
+    Inward,
+    /// the only outward neighbor, or the counter clockwise one when the next ring splits the cell
+    Outward,
+    /// the clockwise outward neighbor, when the next ring splits the cell
+    OutwardClockwise,
+    Clockwise,
+    CounterClockwise,
+    /// the cell right above, on the next level
+    Up,
+    /// the cell right below, on the previous level
+    Down,
 }
 
 impl Neighbor {
-    pub fn iter() -> &'static [Self] {
-        &[
-            Neighbor::North,
-            Neighbor::South,
-            Neighbor::East,
-            Neighbor::West,
-        ]
-    }
-
     pub fn opposite(self) -> Self {
         match self {
             Neighbor::North => Neighbor::South,
             Neighbor::South => Neighbor::North,
             Neighbor::East => Neighbor::West,
             Neighbor::West => Neighbor::East,
+            Neighbor::NorthEast => Neighbor::SouthWest,
+            Neighbor::NorthWest => Neighbor::SouthEast,
+            Neighbor::SouthEast => Neighbor::NorthWest,
+            Neighbor::SouthWest => Neighbor::NorthEast,
+            Neighbor::Inward => Neighbor::Outward,
+            Neighbor::Outward | Neighbor::OutwardClockwise => Neighbor::Inward,
+            Neighbor::Clockwise => Neighbor::CounterClockwise,
+            Neighbor::CounterClockwise => Neighbor::Clockwise,
+            Neighbor::Up => Neighbor::Down,
+            Neighbor::Down => Neighbor::Up,
         }
     }
+
+    /// Directions that never show up on the same grid share a bit, so links fit in a byte
+    fn bit(self) -> u8 {
+        let bit = match self {
+            Neighbor::North | Neighbor::Inward => 0,
+            Neighbor::South | Neighbor::Outward => 1,
+            Neighbor::East | Neighbor::NorthEast | Neighbor::OutwardClockwise => 2,
+            Neighbor::West | Neighbor::NorthWest | Neighbor::Clockwise => 3,
+            Neighbor::SouthEast | Neighbor::CounterClockwise => 4,
+            Neighbor::SouthWest => 5,
+            Neighbor::Up => 6,
+            Neighbor::Down => 7,
+        };
+        1 << bit
+    }
 }
 
-#[derive(Default, Clone, Eq, PartialEq)]
+#[derive(Default, Clone, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct Cell {
     links: Links,
 }
@@ -178,36 +505,22 @@ impl Cell {
     }
 
     pub fn unlink(&mut self, dir: Neighbor) {
-        match dir {
-            Neighbor::North => self.links.north = false,
-            Neighbor::South => self.links.south = false,
-            Neighbor::East => self.links.east = false,
-            Neighbor::West => self.links.west = false,
-        }
+        self.links.0 &= !dir.bit();
     }
 
     pub fn link(&mut self, dir: Neighbor) {
-        match dir {
-            Neighbor::North => self.links.north = true,
-            Neighbor::South => self.links.south = true,
-            Neighbor::East => self.links.east = true,
-            Neighbor::West => self.links.west = true,
-        }
+        self.links.0 |= dir.bit();
     }
 
     pub fn has_link(&self, dir: Neighbor) -> bool {
-        match dir {
-            Neighbor::North => self.links.north,
-            Neighbor::South => self.links.south,
-            Neighbor::East => self.links.east,
-            Neighbor::West => self.links.west,
-        }
+        self.links.has(dir)
     }
 }
 
+/// Neighbors live on the stack, so walking the maze does not allocate
 #[derive(Clone, Copy, Debug)]
 pub struct Neighbors {
-    pub inner: [Option<Neighbor1>; 4],
+    pub inner: [Option<Neighbor1>; MAX_NEIGHBORS],
 }
 
 impl Neighbors {
@@ -216,41 +529,72 @@ impl Neighbors {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
-pub struct Links {
-    pub north: bool,
-    pub south: bool,
-    pub east: bool,
-    pub west: bool,
-}
+/// One bit per direction of the grid, see `Neighbor::bit`. Only ask for directions that exist on
+/// the grid: on hex grids, `East` is the bit of `NorthEast`
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Links(u8);
 
 impl Links {
-    /// returns true if it has at least 1 link
-    pub fn is_partial(&self) -> bool {
-        !self.is_empty() && !self.is_completed()
-    }
-
-    /// returns true if it has all the links
-    pub fn is_completed(&self) -> bool {
-        self.north && self.south && self.east && self.west
+    pub fn has(&self, dir: Neighbor) -> bool {
+        self.0 & dir.bit() != 0
     }
 
     /// returns true if it has no links
     pub fn is_empty(&self) -> bool {
-        !self.north && !self.south && !self.east && !self.west
+        self.0 == 0
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs;
+
+    /// mazes drawn in ascii, see `Maze::from_ascii`
+    fn fixture(name: &str) -> Maze {
+        let path = format!("{}/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+        let ascii = fs::read_to_string(&path).unwrap();
+        let maze = Maze::from_ascii(&ascii).unwrap();
+        assert_eq!(maze.to_ascii(), ascii);
+        maze
+    }
+
+    fn linked(maze: &Maze, cell: usize) -> Vec<(Neighbor, usize)> {
+        maze.linked_neighbors(cell)
+            .iter()
+            .map(|n| (n.dir, n.idx))
+            .collect()
+    }
 
     #[test]
     fn neighbor_at() {
-        let t = BinaryTree::new(7, 3);
-        for c in 0..t.cells.len() {
-            println!("{}: {:?}", c, t.neighbors(c));
-        }
-        panic!()
+        let t = fixture("binary_tree_7x3.txt");
+        let at = |cell, dir| t.neighbor_at(cell, dir).map(|n| n.idx);
+        assert_eq!(at(0, Neighbor::North), None);
+        assert_eq!(at(0, Neighbor::West), None);
+        assert_eq!(at(0, Neighbor::East), Some(1));
+        assert_eq!(at(0, Neighbor::South), Some(7));
+        assert_eq!(at(20, Neighbor::East), None);
+        assert_eq!(at(20, Neighbor::South), None);
+        assert_eq!(at(20, Neighbor::North), Some(13));
+        assert_eq!(at(20, Neighbor::West), Some(19));
+        assert_eq!(linked(&t, 14), vec![(Neighbor::East, 15)]);
+        assert_eq!(
+            linked(&t, 15),
+            vec![(Neighbor::North, 8), (Neighbor::West, 14)]
+        );
+        assert!(t.validate().is_perfect());
+    }
+
+    #[test]
+    fn masked_neighbor_at() {
+        let t = fixture("masked_4x3.txt");
+        assert!(!t.is_enabled(2) && !t.is_enabled(8));
+        assert!(t.neighbor_at(1, Neighbor::East).is_none());
+        assert!(t.neighbor_at(6, Neighbor::North).is_none());
+        assert!(t.neighbor_at(9, Neighbor::West).is_none());
+        assert_eq!(linked(&t, 3), vec![(Neighbor::South, 7)]);
+        assert!(t.validate().is_perfect());
     }
 }
@@ -0,0 +1,254 @@
+use super::{Mask, Maze, Neighbor, Topology, Wrap};
+use crate::grid::Grid;
+
+/// Layout of a single cell: `+---` above it, then `|   ` on its row. Masked cells are filled
+/// with `XXX`
+const CELL_WIDTH: usize = 4;
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum AsciiError {
+    Empty,
+    /// every line must be as long as the first one, `4 * width + 1` columns
+    RaggedLine {
+        line: usize,
+    },
+    /// the last row of cells is not closed by a wall line
+    MissingLine {
+        line: usize,
+    },
+    InvalidChar {
+        line: usize,
+        column: usize,
+        c: char,
+        expected: &'static str,
+    },
+    /// a passage through the outer wall, or into a masked cell
+    OpenWall {
+        line: usize,
+        column: usize,
+    },
+}
+
+impl Maze {
+    /// only flat square mazes without wrapping can be drawn in ascii
+    pub fn has_ascii(&self) -> bool {
+        self.topology == Topology::Square && self.levels == 1 && self.wrap == Wrap::None
+    }
+
+    /// Classic `+---+` / `|   |` drawing of the maze, one text row for each row of cells and
+    /// wall, see `has_ascii`
+    pub fn to_ascii(&self) -> String {
+        assert!(self.has_ascii(), "this maze cannot be drawn in ascii");
+        let width = self.width;
+        let wall = "+---".repeat(width) + "+\n";
+        let mut ascii = String::new();
+        for y in 0..self.height() {
+            for x in 0..width {
+                let cell = y * width + x;
+                ascii.push('+');
+                ascii.push_str(if self.cells[cell].has_link(Neighbor::North) {
+                    "   "
+                } else {
+                    "---"
+                });
+            }
+            ascii.push_str("+\n");
+            for x in 0..width {
+                let cell = y * width + x;
+                ascii.push(if self.cells[cell].has_link(Neighbor::West) {
+                    ' '
+                } else {
+                    '|'
+                });
+                ascii.push_str(if self.mask.is_enabled(cell) {
+                    "   "
+                } else {
+                    "XXX"
+                });
+            }
+            ascii.push_str("|\n");
+        }
+        ascii.push_str(&wall);
+        ascii
+    }
+
+    /// Reads back the drawing of `to_ascii`. Trailing spaces and empty lines are ignored
+    pub fn from_ascii(ascii: &str) -> Result<Self, AsciiError> {
+        let lines: Vec<Vec<char>> = ascii
+            .lines()
+            .map(|l| l.trim_end())
+            .filter(|l| !l.is_empty())
+            .map(|l| l.chars().collect())
+            .collect();
+        let columns = lines.first().map_or(0, |l| l.len());
+        if columns <= CELL_WIDTH {
+            return Err(AsciiError::Empty);
+        }
+        if let Some(y) = lines.iter().position(|l| l.len() != columns) {
+            return Err(AsciiError::RaggedLine { line: y + 1 });
+        }
+        if columns % CELL_WIDTH != 1 {
+            return Err(AsciiError::RaggedLine { line: 1 });
+        }
+        if lines.len() % 2 == 0 {
+            return Err(AsciiError::MissingLine {
+                line: lines.len() + 1,
+            });
+        }
+        let width = columns / CELL_WIDTH;
+        let height = lines.len() / 2;
+
+        let mut mask = Mask::new(width, height);
+        for y in 0..height {
+            let line = 2 * y + 1;
+            for x in 0..width {
+                let column = x * CELL_WIDTH + 1;
+                match read_segment(&lines, line, column, ' ', 'X', "` ` or `X`")? {
+                    Segment::Open => {}
+                    Segment::Closed => mask.disable(y * width + x),
+                }
+            }
+        }
+
+        let mut maze = Self::with_mask(mask);
+        // corners on wall lines, and walls left of cells on the other ones
+        for (y, line) in lines.iter().enumerate() {
+            let expected = if y % 2 == 0 { "`+`" } else { "`|` or ` `" };
+            for x in 0..=width {
+                let column = x * CELL_WIDTH;
+                let c = line[column];
+                let valid = if y % 2 == 0 {
+                    c == '+'
+                } else {
+                    c == '|' || c == ' '
+                };
+                if !valid {
+                    return Err(invalid_char(y, column, c, expected));
+                }
+            }
+        }
+        // walls above each row of cells, and the one below the last row
+        for y in 0..=height {
+            let line = 2 * y;
+            for x in 0..width {
+                let column = x * CELL_WIDTH + 1;
+                if read_segment(&lines, line, column, ' ', '-', "` ` or `-`")? == Segment::Closed {
+                    continue;
+                }
+                let cell = y * width + x;
+                if y == 0 || y == height || !maze.is_enabled(cell) || !maze.is_enabled(cell - width)
+                {
+                    return Err(AsciiError::OpenWall {
+                        line: line + 1,
+                        column: column + 1,
+                    });
+                }
+                maze.link(cell, Neighbor::North);
+            }
+        }
+        // walls on the left of each cell, and the one right of the last column
+        for y in 0..height {
+            let line = 2 * y + 1;
+            for x in 0..=width {
+                let column = x * CELL_WIDTH;
+                if lines[line][column] == '|' {
+                    continue;
+                }
+                let cell = y * width + x;
+                if x == 0 || x == width || !maze.is_enabled(cell) || !maze.is_enabled(cell - 1) {
+                    return Err(AsciiError::OpenWall {
+                        line: line + 1,
+                        column: column + 1,
+                    });
+                }
+                maze.link(cell, Neighbor::West);
+            }
+        }
+        Ok(maze)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum Segment {
+    Open,
+    Closed,
+}
+
+/// the 3 characters inside a cell or along a wall, all of them `open` or `closed`
+fn read_segment(
+    lines: &[Vec<char>],
+    line: usize,
+    column: usize,
+    open: char,
+    closed: char,
+    expected: &'static str,
+) -> Result<Segment, AsciiError> {
+    let segment = &lines[line][column..column + CELL_WIDTH - 1];
+    let first = segment[0];
+    let kind = if first == open {
+        Segment::Open
+    } else if first == closed {
+        Segment::Closed
+    } else {
+        return Err(invalid_char(line, column, first, expected));
+    };
+    match segment.iter().position(|c| *c != first) {
+        Some(i) => Err(invalid_char(line, column + i, segment[i], expected)),
+        None => Ok(kind),
+    }
+}
+
+/// errors count lines and columns from 1, like text editors
+fn invalid_char(line: usize, column: usize, c: char, expected: &'static str) -> AsciiError {
+    AsciiError::InvalidChar {
+        line: line + 1,
+        column: column + 1,
+        c,
+        expected,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors() {
+        let parse = |ascii: &str| Maze::from_ascii(ascii).err();
+        assert_eq!(parse("\n  \n"), Some(AsciiError::Empty));
+        assert_eq!(
+            parse("+---+\n|   |\n+---+---+"),
+            Some(AsciiError::RaggedLine { line: 3 })
+        );
+        assert_eq!(
+            parse("+---+\n|   |"),
+            Some(AsciiError::MissingLine { line: 3 })
+        );
+        assert_eq!(
+            parse("+---+---+\n|   | x |\n+---+---+"),
+            Some(AsciiError::InvalidChar {
+                line: 2,
+                column: 7,
+                c: 'x',
+                expected: "` ` or `X`",
+            })
+        );
+        assert_eq!(
+            parse("+---+---+\n|   |XXX|\n+---+- -+"),
+            Some(AsciiError::InvalidChar {
+                line: 3,
+                column: 7,
+                c: ' ',
+                expected: "` ` or `-`",
+            })
+        );
+        assert_eq!(
+            parse("+---+---+\n|    XXX|\n+---+---+"),
+            Some(AsciiError::OpenWall { line: 2, column: 5 })
+        );
+        assert_eq!(
+            parse("+---+\n|   |\n+   +"),
+            Some(AsciiError::OpenWall { line: 3, column: 2 })
+        );
+    }
+}
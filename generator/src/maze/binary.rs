@@ -0,0 +1,197 @@
+use super::{Cell, Links, Mask, Maze, Topology, Violation, Wrap};
+use crate::save::{read_u16, read_u32, read_u8, SaveError};
+use std::io::{self, Read, Write};
+
+/// Larger mazes are rejected before allocating anything, the size of a corrupt file could take
+/// all the memory
+const MAX_CELLS: usize = 1 << 26;
+/// about as many cells as `MAX_CELLS`, rings get longer the further out they are
+const MAX_RINGS: usize = 1 << 12;
+
+impl Maze {
+    /// Binary layout of the maze, every number is little endian:
+    ///
+    /// | bytes          | field                                                  |
+    /// |----------------|--------------------------------------------------------|
+    /// | 1              | topology, in the order of `Topology`                   |
+    /// | 1              | wrap, in the order of `Wrap`                           |
+    /// | 2              | levels                                                 |
+    /// | 4              | width, for polar mazes the cells of the outermost ring |
+    /// | 4              | height, for polar mazes the rings                      |
+    /// | (mask + 7) / 8 | mask, one bit per cell of a level, set when disabled   |
+    /// | 4              | under cells                                            |
+    /// | 4 per under    | the cell above each under cell                         |
+    /// | 1 per cell     | links, the bits of `Neighbor::bit`                     |
+    pub fn write(&self, w: &mut impl Write) -> io::Result<()> {
+        w.write_all(&[topology_id(self.topology), wrap_id(self.wrap)])?;
+        w.write_all(&(self.levels as u16).to_le_bytes())?;
+        w.write_all(&(self.width as u32).to_le_bytes())?;
+        w.write_all(&(self.height() as u32).to_le_bytes())?;
+        let mut mask = vec![0u8; (self.mask.len() + 7) / 8];
+        for cell in (0..self.mask.len()).filter(|c| !self.mask.is_enabled(*c)) {
+            mask[cell / 8] |= 1 << (cell % 8);
+        }
+        w.write_all(&mask)?;
+        w.write_all(&(self.unders.len() as u32).to_le_bytes())?;
+        for over in &self.unders {
+            w.write_all(&(*over as u32).to_le_bytes())?;
+        }
+        let links: Vec<u8> = self.cells.iter().map(|c| c.links().0).collect();
+        w.write_all(&links)
+    }
+
+    /// Reads back `write`, rejecting links that do not match the grid
+    pub fn read(r: &mut impl Read) -> Result<Self, SaveError> {
+        let topology = topology_from_id(read_u8(r)?)?;
+        let wrap = wrap_from_id(read_u8(r)?)?;
+        let levels = read_u16(r)? as usize;
+        let width = read_u32(r)? as usize;
+        let height = read_u32(r)? as usize;
+        let mut maze = Maze::decoded(topology, wrap, levels, width, height)?;
+
+        let mut mask = vec![0u8; (maze.mask.len() + 7) / 8];
+        r.read_exact(&mut mask)?;
+        for cell in (0..maze.mask.len()).filter(|c| mask[c / 8] & (1 << (c % 8)) != 0) {
+            maze.decode_disabled(cell)?;
+        }
+        let unders = read_u32(r)? as usize;
+        if unders > maze.grid_len() {
+            return Err(SaveError::Corrupt("more under cells than cells"));
+        }
+        for _ in 0..unders {
+            maze.decode_under(read_u32(r)? as usize)?;
+        }
+        let mut links = vec![0u8; maze.cells.len()];
+        r.read_exact(&mut links)?;
+        for (cell, links) in links.into_iter().enumerate() {
+            maze.decode_links(cell, links)?;
+        }
+        maze.check_decoded_links()?;
+        Ok(maze)
+    }
+
+    /// An empty maze read from a file or a document, whose shape may not make sense
+    pub(super) fn decoded(
+        topology: Topology,
+        wrap: Wrap,
+        levels: usize,
+        width: usize,
+        height: usize,
+    ) -> Result<Self, &'static str> {
+        let cells = width
+            .checked_mul(height)
+            .and_then(|c| c.checked_mul(levels))
+            .unwrap_or(usize::MAX);
+        if cells == 0 {
+            return Err("empty maze");
+        }
+        if cells > MAX_CELLS {
+            return Err("maze too large");
+        }
+        if wrap != Wrap::None && topology != Topology::Square {
+            return Err("only square mazes wrap");
+        }
+        if topology != Topology::Polar {
+            let mask = Mask::new(width, height);
+            return Ok(Maze::with_levels(topology, mask, levels).wrapped(wrap));
+        }
+        if height > MAX_RINGS {
+            return Err("maze too large");
+        }
+        if levels != 1 {
+            return Err("polar mazes have a single level");
+        }
+        let maze = Maze::polar(height);
+        if maze.width != width {
+            return Err("polar width does not match its rings");
+        }
+        Ok(maze)
+    }
+
+    /// disables a cell of every level
+    pub(super) fn decode_disabled(&mut self, cell: usize) -> Result<(), &'static str> {
+        if self.topology == Topology::Polar {
+            return Err("polar mazes are not masked");
+        }
+        if cell >= self.mask.len() {
+            return Err("masked cell outside of the maze");
+        }
+        self.mask.disable(cell);
+        Ok(())
+    }
+
+    /// adds an unlinked under cell below `over`
+    pub(super) fn decode_under(&mut self, over: usize) -> Result<(), &'static str> {
+        if self.topology != Topology::Weave {
+            return Err("only weave mazes have under cells");
+        }
+        if over >= self.grid_len() || self.unders.contains(&over) {
+            return Err("under cell without a cell above it");
+        }
+        self.unders.push(over);
+        self.cells.push(Cell::default());
+        Ok(())
+    }
+
+    /// sets the links of a cell, rejecting directions the grid does not have
+    pub(super) fn decode_links(&mut self, cell: usize, links: u8) -> Result<(), &'static str> {
+        let known = self.directions().fold(0, |bits, dir| bits | dir.bit());
+        if links & !known != 0 {
+            return Err("link in a direction the grid does not have");
+        }
+        self.cells[cell].links = Links(links);
+        Ok(())
+    }
+
+    /// links must point to a cell that links back, see `Maze::validate`
+    pub(super) fn check_decoded_links(&self) -> Result<(), &'static str> {
+        let broken = self
+            .validate()
+            .violations
+            .into_iter()
+            .any(|v| matches!(v, Violation::OffGrid { .. } | Violation::Asymmetric { .. }));
+        if broken {
+            Err("broken links")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn topology_id(topology: Topology) -> u8 {
+    match topology {
+        Topology::Square => 0,
+        Topology::Hex => 1,
+        Topology::Polar => 2,
+        Topology::Triangle => 3,
+        Topology::Weave => 4,
+    }
+}
+
+fn topology_from_id(id: u8) -> Result<Topology, SaveError> {
+    match id {
+        0 => Ok(Topology::Square),
+        1 => Ok(Topology::Hex),
+        2 => Ok(Topology::Polar),
+        3 => Ok(Topology::Triangle),
+        4 => Ok(Topology::Weave),
+        _ => Err(SaveError::Corrupt("unknown topology")),
+    }
+}
+
+fn wrap_id(wrap: Wrap) -> u8 {
+    match wrap {
+        Wrap::None => 0,
+        Wrap::Cylinder => 1,
+        Wrap::Torus => 2,
+    }
+}
+
+fn wrap_from_id(id: u8) -> Result<Wrap, SaveError> {
+    match id {
+        0 => Ok(Wrap::None),
+        1 => Ok(Wrap::Cylinder),
+        2 => Ok(Wrap::Torus),
+        _ => Err(SaveError::Corrupt("unknown wrap")),
+    }
+}
@@ -0,0 +1,99 @@
+use super::{Maze, Neighbor1};
+use crate::grid::Grid;
+use rand::prelude::{IteratorRandom, SliceRandom, StdRng};
+use rand::Rng;
+
+impl Maze {
+    /// Removes about `fraction` of the dead ends, linking each one to a neighbor. Neighbors that
+    /// are dead ends too are preferred, so a single link removes two of them
+    pub fn braid(&mut self, rng: &mut StdRng, fraction: f64) {
+        let mut dead_ends = self.dead_ends();
+        dead_ends.shuffle(rng);
+        for cell in dead_ends {
+            // an earlier link may have already removed this one
+            if !self.is_dead_end(cell) || !rng.gen_bool(fraction) {
+                continue;
+            }
+            let links = self.links(cell);
+            let unlinked: Vec<Neighbor1> = self
+                .neighbors(cell)
+                .iter()
+                .filter(|n| !links.has(n.dir))
+                .collect();
+            let neighbor = unlinked
+                .iter()
+                .filter(|n| self.is_dead_end(n.idx))
+                .choose(rng)
+                .or_else(|| unlinked.iter().choose(rng));
+            if let Some(neighbor) = neighbor {
+                self.link(cell, neighbor.dir);
+            }
+        }
+    }
+
+    pub fn dead_ends(&self) -> Vec<usize> {
+        (0..self.len()).filter(|c| self.is_dead_end(*c)).collect()
+    }
+
+    /// cells with a single link
+    pub fn is_dead_end(&self, cell: usize) -> bool {
+        self.linked_neighbors(cell).iter().count() == 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{MazeGenerator, RecurBacktrackerGen};
+    use crate::maze::Neighbor;
+    use rand::SeedableRng;
+
+    #[test]
+    fn braids() {
+        let mut generator = RecurBacktrackerGen::new(Maze::new(8, 8));
+        generator.finish(&mut StdRng::seed_from_u64(6));
+        let maze = generator.maze();
+        assert!(!maze.dead_ends().is_empty());
+
+        let mut unchanged = maze.clone();
+        unchanged.braid(&mut StdRng::seed_from_u64(1), 0.0);
+        assert!(&unchanged == maze);
+
+        let mut braided = maze.clone();
+        braided.braid(&mut StdRng::seed_from_u64(1), 1.0);
+        assert!(braided.dead_ends().is_empty());
+        let mut again = maze.clone();
+        again.braid(&mut StdRng::seed_from_u64(1), 0.5);
+        let mut half = maze.clone();
+        half.braid(&mut StdRng::seed_from_u64(1), 0.5);
+        assert!(again == half);
+
+        // 0 1 2 3   1 is a dead end between the dead end 0 and 2, which goes on to 3 and 6
+        // 4 5 6 7
+        // 8 9 A B
+        let mut maze = Maze::new(4, 3);
+        let links = [
+            (0, Neighbor::South),
+            (4, Neighbor::South),
+            (8, Neighbor::East),
+            (9, Neighbor::North),
+            (5, Neighbor::North),
+            (9, Neighbor::East),
+            (10, Neighbor::North),
+            (6, Neighbor::North),
+            (2, Neighbor::East),
+            (3, Neighbor::South),
+            (7, Neighbor::South),
+        ];
+        for (cell, dir) in links.iter().cloned() {
+            maze.link(cell, dir);
+        }
+        assert_eq!(maze.dead_ends(), vec![0, 1, 11]);
+        for seed in 0..10 {
+            let mut braided = maze.clone();
+            braided.braid(&mut StdRng::seed_from_u64(seed), 1.0);
+            assert!(braided.links(1).has(Neighbor::West));
+            assert!(!braided.links(1).has(Neighbor::East));
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use super::{Maze, Transform};
+use std::collections::HashSet;
+
+/// FNV-1a, simple and the same on every platform and release of rust unlike `DefaultHasher`
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+impl Maze {
+    fn bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        let written = if self.unders.is_empty() {
+            self.write(&mut bytes)
+        } else {
+            self.with_sorted_unders().write(&mut bytes)
+        };
+        written.expect("writing to memory never fails");
+        bytes
+    }
+
+    /// The same maze with its under cells in the order of the cells they pass under, rather
+    /// than the order the tunnels were dug in. Every cell has at most one tunnel under it
+    fn with_sorted_unders(&self) -> Self {
+        let grid_len = self.cells.len() - self.unders.len();
+        let mut order: Vec<_> = (0..self.unders.len()).collect();
+        order.sort_by_key(|under| self.unders[*under]);
+        let mut maze = self.clone();
+        for (to, from) in order.into_iter().enumerate() {
+            maze.unders[to] = self.unders[from];
+            maze.cells[grid_len + to] = self.cells[grid_len + from].clone();
+        }
+        maze
+    }
+
+    /// Hash of the layout that can be stored, it only changes along with `save::VERSION`. Rooms
+    /// that are not opened yet are left out, like in save files, and tunnels hash the same in
+    /// whatever order they were dug
+    pub fn content_hash(&self) -> u64 {
+        fnv1a(&self.bytes())
+    }
+
+    /// The same maze for all the rotations and reflections of a square grid, the one that comes
+    /// first byte by byte. Other grids are their own canonical form
+    pub fn canonical(&self) -> Self {
+        let mut canonical = (self.bytes(), None);
+        for transform in Transform::ALL.iter() {
+            if let Some(maze) = self.transformed(*transform) {
+                let bytes = maze.bytes();
+                if bytes < canonical.0 {
+                    canonical = (bytes, Some(maze));
+                }
+            }
+        }
+        canonical.1.unwrap_or_else(|| self.clone())
+    }
+
+    /// `content_hash` of the canonical form
+    pub fn canonical_hash(&self) -> u64 {
+        self.canonical().content_hash()
+    }
+}
+
+/// Keeps the first maze of every layout, rotated and reflected mazes are the same layout.
+/// Layouts compare like `content_hash`, rooms not opened yet are left out
+pub fn dedup(mazes: impl IntoIterator<Item = Maze>) -> Vec<Maze> {
+    let mut layouts = HashSet::new();
+    mazes
+        .into_iter()
+        .filter(|maze| layouts.insert(maze.canonical().bytes()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{MazeGenerator, SidewinderGen};
+    use crate::grid::Grid;
+    use crate::maze::{Mask, Neighbor, Room, Topology};
+    use rand::{prelude::StdRng, SeedableRng};
+
+    #[test]
+    fn hashes() {
+        let mut maze = Maze::new(2, 1);
+        maze.link(0, Neighbor::East);
+        // pinned, so that stored hashes stay valid
+        assert_eq!(maze.content_hash(), fnv1a(&maze.bytes()));
+        assert_eq!(maze.content_hash(), 0x9c42_1c70_6f0a_af49);
+
+        let mut generator = SidewinderGen::new(Maze::new(6, 4));
+        generator.finish(&mut StdRng::seed_from_u64(9));
+        let maze = generator.maze().clone();
+        let variants: Vec<_> = Transform::ALL
+            .iter()
+            .map(|t| maze.transformed(*t).unwrap())
+            .collect();
+        for variant in &variants {
+            assert_ne!(variant.content_hash(), maze.content_hash());
+            assert_eq!(variant.canonical_hash(), maze.canonical_hash());
+        }
+        let mut other = SidewinderGen::new(Maze::new(6, 4));
+        other.finish(&mut StdRng::seed_from_u64(10));
+        let room = Room {
+            level: 0,
+            x: 1,
+            y: 1,
+            width: 2,
+            height: 2,
+            doors: 1,
+        };
+        let mut mazes = vec![maze.clone(), other.maze().clone()];
+        mazes.extend(variants);
+        mazes.push(maze.clone().with_rooms(vec![room]));
+        let unique = dedup(mazes);
+        assert_eq!(unique.len(), 2);
+        assert!(unique[0] == maze);
+    }
+
+    #[test]
+    fn tunnel_order() {
+        // 1 and 3 go down through 6 and 8, both crossed by tunnels from 5 to 9
+        let mut maze = Maze::with_topology(Topology::Weave, Mask::new(5, 3));
+        for cell in [1, 6, 3, 8].iter().cloned() {
+            maze.link(cell, Neighbor::South);
+        }
+        let mut forward = maze.clone();
+        forward.tunnel(5, Neighbor::East);
+        forward.tunnel(7, Neighbor::East);
+        let mut backward = maze;
+        backward.tunnel(7, Neighbor::East);
+        backward.tunnel(5, Neighbor::East);
+        assert!(forward != backward);
+        assert_eq!(forward.content_hash(), backward.content_hash());
+        assert_eq!(forward.canonical_hash(), backward.canonical_hash());
+        assert_eq!(dedup(vec![forward, backward]).len(), 1);
+    }
+}
@@ -0,0 +1,68 @@
+use super::{Maze, Neighbor};
+
+impl Maze {
+    /// Even columns sit half a cell higher than odd ones, so the diagonal neighbors of an even
+    /// column are one row up compared to the ones of an odd column
+    pub(super) fn hex_neighbor_at(&self, cell: usize, n: Neighbor) -> Option<usize> {
+        let row = (cell / self.width) as isize;
+        let col = (cell % self.width) as isize;
+        let north_diagonal = if col % 2 == 0 { row - 1 } else { row };
+        let south_diagonal = north_diagonal + 1;
+        let (row, col) = match n {
+            Neighbor::North => (row - 1, col),
+            Neighbor::South => (row + 1, col),
+            Neighbor::NorthEast => (north_diagonal, col + 1),
+            Neighbor::NorthWest => (north_diagonal, col - 1),
+            Neighbor::SouthEast => (south_diagonal, col + 1),
+            Neighbor::SouthWest => (south_diagonal, col - 1),
+            _ => return None,
+        };
+        if row < 0 || col < 0 || row >= self.height() as isize || col >= self.width as isize {
+            None
+        } else {
+            Some(row as usize * self.width + col as usize)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+    use crate::maze::{Mask, Topology};
+
+    #[test]
+    fn neighbor_at() {
+        let mut maze = Maze::with_topology(Topology::Hex, Mask::new(4, 3));
+        let at = |maze: &Maze, cell, dir| maze.neighbor_at(cell, dir).map(|n| n.idx);
+        // even columns are higher, their north diagonals are on the row above
+        assert_eq!(at(&maze, 0, Neighbor::North), None);
+        assert_eq!(at(&maze, 0, Neighbor::NorthEast), None);
+        assert_eq!(at(&maze, 0, Neighbor::NorthWest), None);
+        assert_eq!(at(&maze, 0, Neighbor::SouthEast), Some(1));
+        assert_eq!(at(&maze, 0, Neighbor::South), Some(4));
+        assert_eq!(at(&maze, 6, Neighbor::NorthEast), Some(3));
+        assert_eq!(at(&maze, 6, Neighbor::NorthWest), Some(1));
+        assert_eq!(at(&maze, 6, Neighbor::SouthEast), Some(7));
+        assert_eq!(at(&maze, 6, Neighbor::SouthWest), Some(5));
+        // odd columns are lower, their south diagonals are on the row below
+        assert_eq!(at(&maze, 1, Neighbor::NorthEast), Some(2));
+        assert_eq!(at(&maze, 1, Neighbor::NorthWest), Some(0));
+        assert_eq!(at(&maze, 1, Neighbor::SouthEast), Some(6));
+        assert_eq!(at(&maze, 1, Neighbor::SouthWest), Some(4));
+        assert_eq!(at(&maze, 11, Neighbor::SouthEast), None);
+        assert_eq!(at(&maze, 11, Neighbor::South), None);
+        assert_eq!(at(&maze, 11, Neighbor::NorthEast), None);
+        assert_eq!(at(&maze, 11, Neighbor::NorthWest), Some(10));
+
+        maze.link(1, Neighbor::SouthEast);
+        let back: Vec<_> = maze
+            .linked_neighbors(6)
+            .iter()
+            .map(|n| (n.dir, n.idx))
+            .collect();
+        assert_eq!(back, vec![(Neighbor::NorthWest, 1)]);
+        maze.unlink(6, Neighbor::NorthWest);
+        assert!(maze.links(1).is_empty() && maze.links(6).is_empty());
+    }
+}
@@ -0,0 +1,218 @@
+use super::{Links, Maze, Topology, Wrap};
+use crate::grid::Grid;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+/// bumped whenever the documents change, see `schema/maze.schema.json`
+pub const JSON_VERSION: u32 = 1;
+
+/// How the passages of a maze are written down
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum JsonFormat {
+    /// the links of every cell, the way the maze keeps them
+    Bitmasks,
+    /// the walls between cells, handier for drawing
+    Walls,
+}
+
+/// A maze as a JSON document, for tools that cannot link against the plugin. `Maze` serializes
+/// as its `bitmasks` document, and deserializes from either of them.
+///
+/// Cells are numbered like the maze does: row by row (ring by ring on polar mazes), one level
+/// after the other, then the under cells of weave mazes
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MazeDocument {
+    pub version: u32,
+    pub topology: Topology,
+    pub wrap: Wrap,
+    pub levels: usize,
+    /// cells of a row, for polar mazes the cells of the outermost ring
+    pub width: usize,
+    /// rows of a level, for polar mazes the rings
+    pub height: usize,
+    /// masked cells of the first level, every level is masked the same way
+    pub disabled: Vec<usize>,
+    /// the cell above each under cell
+    pub unders: Vec<usize>,
+    #[serde(flatten)]
+    pub passages: Passages,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "format", rename_all = "snake_case")]
+pub enum Passages {
+    /// links of every cell, as the bits of `Neighbor::bit`
+    Bitmasks { cells: Vec<Links> },
+    /// pairs of neighbors with a wall between them, lowest cell first. The other neighbors are
+    /// linked
+    Walls { walls: Vec<(usize, usize)> },
+}
+
+impl Maze {
+    /// pairs of neighbors that are not linked, from either side
+    fn walls(&self) -> Vec<(usize, usize)> {
+        let mut walls = vec![];
+        for cell in (0..self.grid_len()).filter(|c| self.is_enabled(*c)) {
+            for n in self.neighbors(cell).iter().filter(|n| cell < n.idx) {
+                let back = self.back(cell, n);
+                if !self.cells[cell].has_link(n.dir) && !self.cells[n.idx].has_link(back) {
+                    walls.push((cell, n.idx));
+                }
+            }
+        }
+        walls
+    }
+}
+
+impl MazeDocument {
+    pub fn new(maze: &Maze, format: JsonFormat) -> Self {
+        let passages = match format {
+            JsonFormat::Bitmasks => Passages::Bitmasks {
+                cells: maze.cells.iter().map(|c| c.links()).collect(),
+            },
+            JsonFormat::Walls => Passages::Walls {
+                walls: maze.walls(),
+            },
+        };
+        Self {
+            version: JSON_VERSION,
+            topology: maze.topology,
+            wrap: maze.wrap,
+            levels: maze.levels,
+            width: maze.width,
+            height: maze.height(),
+            disabled: (0..maze.mask.len())
+                .filter(|c| !maze.mask.is_enabled(*c))
+                .collect(),
+            unders: maze.unders.clone(),
+            passages,
+        }
+    }
+}
+
+impl From<Maze> for MazeDocument {
+    fn from(maze: Maze) -> Self {
+        Self::new(&maze, JsonFormat::Bitmasks)
+    }
+}
+
+impl TryFrom<MazeDocument> for Maze {
+    type Error = &'static str;
+
+    fn try_from(document: MazeDocument) -> Result<Self, Self::Error> {
+        if document.version != JSON_VERSION {
+            return Err("unsupported version");
+        }
+        let mut maze = Maze::decoded(
+            document.topology,
+            document.wrap,
+            document.levels,
+            document.width,
+            document.height,
+        )?;
+        for cell in document.disabled {
+            maze.decode_disabled(cell)?;
+        }
+        for over in document.unders {
+            maze.decode_under(over)?;
+        }
+        match document.passages {
+            Passages::Bitmasks { cells } => {
+                if cells.len() != maze.cells.len() {
+                    return Err("one bitmask per cell expected");
+                }
+                for (cell, links) in cells.into_iter().enumerate() {
+                    maze.decode_links(cell, links.0)?;
+                }
+            }
+            Passages::Walls { walls } => {
+                if !maze.unders.is_empty() {
+                    return Err("tunnels are only read from bitmasks");
+                }
+                let mut walls: HashSet<_> = walls.into_iter().collect();
+                for cell in 0..maze.len() {
+                    if !maze.is_enabled(cell) {
+                        continue;
+                    }
+                    for n in maze.neighbors(cell).iter().filter(|n| cell < n.idx) {
+                        if !walls.remove(&(cell, n.idx)) {
+                            maze.link(cell, n.dir);
+                        }
+                    }
+                }
+                if !walls.is_empty() {
+                    return Err("wall between cells that are not neighbors");
+                }
+            }
+        }
+        maze.check_decoded_links()?;
+        Ok(maze)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra::Solution;
+    use crate::generator::{MazeGenerator, RecurBacktrackerGen};
+    use crate::maze::{Mask, Neighbor};
+    use rand::{prelude::StdRng, SeedableRng};
+
+    fn generated(maze: Maze) -> Maze {
+        let mut generator = RecurBacktrackerGen::new(maze);
+        generator.finish(&mut StdRng::seed_from_u64(7));
+        generator.maze().clone()
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut mask = Mask::new(5, 4);
+        mask.disable(3);
+        let mazes = vec![
+            generated(Maze::with_mask(mask)),
+            generated(Maze::new(4, 4).wrapped(Wrap::Torus)),
+            generated(Maze::with_levels(Topology::Triangle, Mask::new(5, 3), 2)),
+            generated(Maze::polar(3)),
+        ];
+        for maze in mazes {
+            for format in [JsonFormat::Bitmasks, JsonFormat::Walls].iter() {
+                let json = serde_json::to_string(&MazeDocument::new(&maze, *format)).unwrap();
+                let read: Maze = serde_json::from_str(&json).unwrap();
+                assert!(read == maze, "{}", json);
+            }
+        }
+        let weave = generated(Maze::with_topology(Topology::Weave, Mask::new(6, 6)));
+        let json = serde_json::to_string(&weave).unwrap();
+        assert!(serde_json::from_str::<Maze>(&json).unwrap() == weave);
+    }
+
+    #[test]
+    fn documents() {
+        let mut maze = Maze::new(2, 1);
+        maze.link(0, Neighbor::East);
+        let bitmasks = serde_json::to_value(&maze).unwrap();
+        assert_eq!(bitmasks["format"], "bitmasks");
+        assert_eq!(bitmasks["topology"], "Square");
+        assert_eq!(bitmasks["cells"], serde_json::json!([4, 8]));
+        let walls = MazeDocument::new(&Maze::new(2, 1), JsonFormat::Walls);
+        let walls = serde_json::to_value(&walls).unwrap();
+        assert_eq!(walls["format"], "walls");
+        assert_eq!(walls["walls"], serde_json::json!([[0, 1]]));
+        let solution = serde_json::to_value(&Solution::new(&Maze::new(2, 1), 0)).unwrap();
+        assert_eq!(solution["distances"], serde_json::json!([0, null]));
+
+        let mut broken = bitmasks.clone();
+        broken["cells"] = serde_json::json!([4, 0]);
+        let error = serde_json::from_value::<Maze>(broken).err().unwrap();
+        assert_eq!(error.to_string(), "broken links");
+        // down, on a single level
+        let mut unknown = bitmasks;
+        unknown["cells"] = serde_json::json!([4, 8 | 128]);
+        let error = serde_json::from_value::<Maze>(unknown).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "link in a direction the grid does not have"
+        );
+    }
+}
@@ -0,0 +1,34 @@
+use super::{Maze, Neighbor};
+
+impl Maze {
+    /// Levels are stacked: every cell links up to the cell right above it, and down to the one
+    /// right below
+    pub(super) fn level_neighbor_at(&self, cell: usize, n: Neighbor) -> Option<usize> {
+        let level_len = self.mask.len();
+        match n {
+            Neighbor::Up if self.level(cell) + 1 < self.levels => Some(cell + level_len),
+            Neighbor::Down if self.level(cell) > 0 => Some(cell - level_len),
+            _ => None,
+        }
+    }
+
+    pub fn levels(&self) -> usize {
+        self.levels
+    }
+
+    /// level of a cell, under cells are on the level of the cell above them
+    pub fn level(&self, cell: usize) -> usize {
+        self.over_cell(cell).unwrap_or(cell) / self.mask.len()
+    }
+
+    /// index of a cell within its level, every level is laid out like the first one
+    pub fn level_cell(&self, cell: usize) -> usize {
+        self.over_cell(cell).unwrap_or(cell) % self.mask.len()
+    }
+
+    /// cells linked to another level
+    pub fn is_stairs(&self, cell: usize) -> bool {
+        let links = self.cells[cell].links();
+        links.has(Neighbor::Up) || links.has(Neighbor::Down)
+    }
+}
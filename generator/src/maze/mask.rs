@@ -0,0 +1,221 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+/// Set of disabled cells. Masked cells are never linked, so the maze takes the shape of the
+/// enabled ones. The enabled cells must be connected, otherwise generators like wilson never end,
+/// masks read from files are checked for that.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Mask {
+    width: usize,
+    len: usize,
+    /// one bit per cell
+    disabled: Vec<u64>,
+}
+
+#[derive(Debug)]
+pub enum MaskError {
+    Io(io::Error),
+    Png(png::DecodingError),
+    /// no cells, or none of them enabled
+    Empty,
+    RaggedLine {
+        line: usize,
+    },
+    InvalidChar {
+        line: usize,
+        column: usize,
+        c: char,
+    },
+    /// the enabled cells are not all connected to each other, by their sides
+    Disconnected,
+}
+
+impl From<io::Error> for MaskError {
+    fn from(e: io::Error) -> Self {
+        MaskError::Io(e)
+    }
+}
+
+impl From<png::DecodingError> for MaskError {
+    fn from(e: png::DecodingError) -> Self {
+        MaskError::Png(e)
+    }
+}
+
+impl Mask {
+    /// A mask with every cell enabled
+    pub fn new(width: usize, height: usize) -> Self {
+        let len = width * height;
+        Self {
+            width,
+            len,
+            disabled: vec![0; (len + 63) / 64],
+        }
+    }
+
+    /// Loads a `.png` image or an ascii template, depending on the extension
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MaskError> {
+        let path = path.as_ref();
+        let mut file = BufReader::new(File::open(path)?);
+        if path.extension().map_or(false, |ext| ext == "png") {
+            Self::from_png(file)
+        } else {
+            let mut template = String::new();
+            file.read_to_string(&mut template)?;
+            Self::from_ascii(&template)
+        }
+    }
+
+    /// One line per row: `X` disables a cell, `.` leaves it enabled
+    pub fn from_ascii(template: &str) -> Result<Self, MaskError> {
+        let lines: Vec<_> = template
+            .lines()
+            .map(|l| l.trim_end())
+            .filter(|l| !l.is_empty())
+            .collect();
+        let width = lines.first().map_or(0, |l| l.chars().count());
+        if width == 0 {
+            return Err(MaskError::Empty);
+        }
+        let mut mask = Self::new(width, lines.len());
+        for (y, line) in lines.iter().enumerate() {
+            if line.chars().count() != width {
+                return Err(MaskError::RaggedLine { line: y + 1 });
+            }
+            for (x, c) in line.chars().enumerate() {
+                match c {
+                    'X' => mask.disable(y * width + x),
+                    '.' => {}
+                    c => {
+                        return Err(MaskError::InvalidChar {
+                            line: y + 1,
+                            column: x + 1,
+                            c,
+                        })
+                    }
+                }
+            }
+        }
+        mask.connected()
+    }
+
+    /// One pixel per cell: dark pixels disable a cell, light ones leave it enabled
+    pub fn from_png(reader: impl Read) -> Result<Self, MaskError> {
+        let (width, height, dark) = dark_pixels(reader)?;
+        if width == 0 || height == 0 {
+            return Err(MaskError::Empty);
+        }
+        let mut mask = Self::new(width, height);
+        for cell in (0..dark.len()).filter(|c| dark[*c]) {
+            mask.disable(cell);
+        }
+        mask.connected()
+    }
+
+    /// Floods the enabled cells from the first one, through their sides. Triangles only have one
+    /// neighbor above or below, `Maze::connects` checks them once the topology is known
+    fn connected(self) -> Result<Self, MaskError> {
+        let first = (0..self.len).find(|c| self.is_enabled(*c));
+        let first = first.ok_or(MaskError::Empty)?;
+        let mut reached = vec![false; self.len];
+        reached[first] = true;
+        let mut pending = vec![first];
+        while let Some(cell) = pending.pop() {
+            let (x, y) = (cell % self.width, cell / self.width);
+            let sides = [
+                (x > 0, cell.wrapping_sub(1)),
+                (x + 1 < self.width, cell + 1),
+                (y > 0, cell.wrapping_sub(self.width)),
+                (y + 1 < self.height(), cell + self.width),
+            ];
+            for (inside, side) in sides.iter().cloned() {
+                if inside && !reached[side] && self.is_enabled(side) {
+                    reached[side] = true;
+                    pending.push(side);
+                }
+            }
+        }
+        if (0..self.len).all(|c| reached[c] || !self.is_enabled(c)) {
+            Ok(self)
+        } else {
+            Err(MaskError::Disconnected)
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.len / self.width
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_enabled(&self, cell: usize) -> bool {
+        assert!(cell < self.len, "cell {} outside of the mask", cell);
+        self.disabled[cell / 64] & (1 << (cell % 64)) == 0
+    }
+
+    pub fn disable(&mut self, cell: usize) {
+        assert!(cell < self.len, "cell {} outside of the mask", cell);
+        self.disabled[cell / 64] |= 1 << (cell % 64);
+    }
+}
+
+/// Width, height and whether each pixel is dark, row by row
+pub(super) fn dark_pixels(
+    reader: impl Read,
+) -> Result<(usize, usize, Vec<bool>), png::DecodingError> {
+    let mut decoder = png::Decoder::new(reader);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let (info, mut reader) = decoder.read_info()?;
+    let mut buf = vec![0; info.buffer_size()];
+    reader.next_frame(&mut buf)?;
+    let width = info.width as usize;
+    let height = info.height as usize;
+    let channels = info.color_type.samples();
+    let mut dark = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let row = &buf[y * info.line_size..];
+        for x in 0..width {
+            let pixel = &row[x * channels..(x + 1) * channels];
+            // grayscale keeps its value, rgb gets averaged; alpha is ignored
+            let luma = if channels < 3 {
+                pixel[0] as u32
+            } else {
+                (pixel[0] as u32 + pixel[1] as u32 + pixel[2] as u32) / 3
+            };
+            dark.push(luma < 128);
+        }
+    }
+    Ok((width, height, dark))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{Maze, Topology};
+
+    #[test]
+    fn connected() {
+        assert!(Mask::from_ascii("..X\nX..\n").is_ok());
+        assert!(matches!(
+            Mask::from_ascii(".X.\n.X.\n"),
+            Err(MaskError::Disconnected)
+        ));
+        // corners do not connect cells
+        assert!(matches!(
+            Mask::from_ascii(".X\nX.\n"),
+            Err(MaskError::Disconnected)
+        ));
+        assert!(matches!(Mask::from_ascii("XX\n"), Err(MaskError::Empty)));
+        // the right column points down then up, its triangles do not touch
+        let column = Mask::from_ascii("X.\nX.\n").unwrap();
+        assert!(Maze::connects(Topology::Square, &column));
+        assert!(!Maze::connects(Topology::Triangle, &column));
+    }
+}
@@ -0,0 +1,152 @@
+use super::{Maze, Neighbor};
+use std::f32::consts::PI;
+
+/// cells on the innermost ring, which surrounds an empty hub
+const INNER_RING_CELLS: usize = 6;
+
+/// Concentric rings of cells, numbered clockwise from the innermost ring outwards. Whenever the
+/// cells of a ring would get too wide, the next ring splits each of them in two, so a cell has
+/// one inward neighbor and one or two outward ones.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct PolarGrid {
+    /// index of the first cell of every ring
+    offsets: Vec<usize>,
+    len: usize,
+}
+
+impl PolarGrid {
+    pub fn new(rings: usize) -> Self {
+        let mut offsets = Vec::with_capacity(rings);
+        let mut len = 0;
+        let mut ring_len = INNER_RING_CELLS;
+        for ring in 0..rings {
+            if ring > 0 {
+                // the hub takes the space of one ring, so ring `n` starts at radius `n + 1`
+                let circumference = 2.0 * PI * (ring + 1) as f32;
+                if circumference / ring_len as f32 >= 1.5 {
+                    ring_len *= 2;
+                }
+            }
+            offsets.push(len);
+            len += ring_len;
+        }
+        Self { offsets, len }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn rings(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn ring_len(&self, ring: usize) -> usize {
+        let end = self.offsets.get(ring + 1).cloned().unwrap_or(self.len);
+        end - self.offsets[ring]
+    }
+
+    /// ring and position inside the ring of a cell
+    pub fn locate(&self, cell: usize) -> (usize, usize) {
+        let ring = self.offsets.partition_point(|offset| *offset <= cell) - 1;
+        (ring, cell - self.offsets[ring])
+    }
+
+    /// how many cells of `ring` share a single cell of the ring inside it
+    pub fn split(&self, ring: usize) -> usize {
+        if ring == 0 {
+            1
+        } else {
+            self.ring_len(ring) / self.ring_len(ring - 1)
+        }
+    }
+
+    fn cell(&self, ring: usize, pos: usize) -> usize {
+        self.offsets[ring] + pos
+    }
+
+    fn neighbor_at(&self, cell: usize, n: Neighbor) -> Option<usize> {
+        let (ring, pos) = self.locate(cell);
+        let len = self.ring_len(ring);
+        match n {
+            Neighbor::Clockwise => Some(self.cell(ring, (pos + 1) % len)),
+            Neighbor::CounterClockwise => Some(self.cell(ring, (pos + len - 1) % len)),
+            Neighbor::Inward if ring > 0 => Some(self.cell(ring - 1, pos / self.split(ring))),
+            Neighbor::Outward if ring + 1 < self.rings() => {
+                Some(self.cell(ring + 1, pos * self.split(ring + 1)))
+            }
+            Neighbor::OutwardClockwise if ring + 1 < self.rings() && self.split(ring + 1) == 2 => {
+                Some(self.cell(ring + 1, pos * 2 + 1))
+            }
+            _ => None,
+        }
+    }
+
+    /// direction that leads from the inward neighbor of `cell` back to `cell`
+    fn outward_dir(&self, cell: usize) -> Neighbor {
+        let (ring, pos) = self.locate(cell);
+        if self.split(ring) == 2 && pos % 2 == 1 {
+            Neighbor::OutwardClockwise
+        } else {
+            Neighbor::Outward
+        }
+    }
+}
+
+impl Maze {
+    pub(super) fn polar_neighbor_at(&self, cell: usize, n: Neighbor) -> Option<usize> {
+        self.polar_grid()
+            .expect("not a polar maze")
+            .neighbor_at(cell, n)
+    }
+
+    pub(super) fn polar_outward_dir(&self, cell: usize) -> Neighbor {
+        self.polar_grid()
+            .expect("not a polar maze")
+            .outward_dir(cell)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+
+    #[test]
+    fn neighbor_at() {
+        // rings of 6, 12, 24 and 24 cells
+        let mut maze = Maze::polar(4);
+        let grid = maze.polar_grid().unwrap().clone();
+        assert_eq!(grid.len(), 66);
+        assert_eq!((grid.split(1), grid.split(2), grid.split(3)), (2, 2, 1));
+        assert_eq!(grid.locate(18), (2, 0));
+        let at = |maze: &Maze, cell, dir| maze.neighbor_at(cell, dir).map(|n| n.idx);
+        // rings wrap around
+        assert_eq!(at(&maze, 0, Neighbor::CounterClockwise), Some(5));
+        assert_eq!(at(&maze, 5, Neighbor::Clockwise), Some(0));
+        assert_eq!(at(&maze, 65, Neighbor::Clockwise), Some(42));
+        // a split ring has two outward neighbors, both going back to the same cell
+        assert_eq!(at(&maze, 0, Neighbor::Inward), None);
+        assert_eq!(at(&maze, 0, Neighbor::Outward), Some(6));
+        assert_eq!(at(&maze, 0, Neighbor::OutwardClockwise), Some(7));
+        assert_eq!(at(&maze, 6, Neighbor::Inward), Some(0));
+        assert_eq!(at(&maze, 7, Neighbor::Inward), Some(0));
+        // an unsplit ring only has one
+        assert_eq!(at(&maze, 18, Neighbor::Outward), Some(42));
+        assert_eq!(at(&maze, 18, Neighbor::OutwardClockwise), None);
+        assert_eq!(at(&maze, 42, Neighbor::Inward), Some(18));
+        assert_eq!(at(&maze, 42, Neighbor::Outward), None);
+
+        maze.link(7, Neighbor::Inward);
+        assert!(maze.links(0).has(Neighbor::OutwardClockwise));
+        assert!(!maze.links(0).has(Neighbor::Outward));
+        let back: Vec<_> = maze
+            .linked_neighbors(0)
+            .iter()
+            .map(|n| (n.dir, n.idx))
+            .collect();
+        assert_eq!(back, vec![(Neighbor::OutwardClockwise, 7)]);
+        maze.unlink(0, Neighbor::OutwardClockwise);
+        assert!(maze.links(0).is_empty() && maze.links(7).is_empty());
+    }
+}
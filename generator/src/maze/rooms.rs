@@ -0,0 +1,174 @@
+use super::{Maze, Neighbor, Topology};
+use crate::grid::Grid;
+use rand::prelude::{SliceRandom, StdRng};
+
+/// Rectangle of cells on a level, carved as a single open area
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Room {
+    pub level: usize,
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    /// passages between the room and the cells around it
+    pub doors: usize,
+}
+
+impl Room {
+    fn contains(&self, maze: &Maze, cell: usize) -> bool {
+        let level_len = maze.mask.len();
+        let (x, y) = (cell % level_len % maze.width, cell % level_len / maze.width);
+        cell / level_len == self.level
+            && (self.x..self.x + self.width).contains(&x)
+            && (self.y..self.y + self.height).contains(&y)
+    }
+}
+
+impl Maze {
+    /// Reserves rooms, their cells are disabled until `open_rooms` so generators carve corridors
+    /// around them. Like masks, rooms must not cut the rest of the maze in pieces. Rooms are only
+    /// kept until they are opened, they are not saved
+    pub fn with_rooms(self, rooms: Vec<Room>) -> Self {
+        assert_ne!(self.topology, Topology::Polar, "polar mazes have no rooms");
+        for room in &rooms {
+            assert!(self.is_inside(room), "room {:?} outside of the grid", room);
+        }
+        let maze = self.reserved(rooms);
+        assert!(maze.is_connected(), "the rooms split the maze in pieces");
+        maze
+    }
+
+    /// Whether the rooms can be reserved by `with_rooms`
+    pub fn fits_rooms(&self, rooms: &[Room]) -> bool {
+        self.topology != Topology::Polar
+            && rooms.iter().all(|room| self.is_inside(room))
+            && self.clone().reserved(rooms.to_vec()).is_connected()
+    }
+
+    fn is_inside(&self, room: &Room) -> bool {
+        room.level < self.levels
+            && room.x + room.width <= self.width
+            && room.y + room.height <= self.height()
+    }
+
+    fn reserved(mut self, rooms: Vec<Room>) -> Self {
+        self.in_rooms = vec![];
+        if !rooms.is_empty() {
+            self.in_rooms = vec![0; (self.grid_len() + 63) / 64];
+            for cell in 0..self.grid_len() {
+                if rooms.iter().any(|room| room.contains(&self, cell)) {
+                    self.in_rooms[cell / 64] |= 1 << (cell % 64);
+                }
+            }
+        }
+        self.rooms = rooms;
+        self
+    }
+
+    /// rooms waiting to be opened
+    pub fn rooms(&self) -> &[Room] {
+        &self.rooms
+    }
+
+    pub(super) fn in_room(&self, cell: usize) -> bool {
+        self.in_rooms
+            .get(cell / 64)
+            .map_or(false, |bits| bits & (1 << (cell % 64)) != 0)
+    }
+
+    /// Enables the cells of the rooms, links them all together and opens the doors of every room
+    /// in random walls around it, on its own level
+    pub fn open_rooms(&mut self, rng: &mut StdRng) {
+        let rooms = std::mem::take(&mut self.rooms);
+        self.in_rooms = vec![];
+        for room in &rooms {
+            let cells: Vec<_> = (0..self.grid_len())
+                .filter(|c| room.contains(self, *c))
+                .collect();
+            let mut walls = vec![];
+            for cell in cells {
+                for n in self.neighbors(cell).iter() {
+                    if n.dir == Neighbor::Up || n.dir == Neighbor::Down {
+                        // doors are not stairs
+                        continue;
+                    }
+                    if !room.contains(self, n.idx) {
+                        walls.push((cell, n.dir));
+                    } else if cell < n.idx {
+                        self.link(cell, n.dir);
+                    }
+                }
+            }
+            let doors: Vec<_> = walls.choose_multiple(rng, room.doors).cloned().collect();
+            for (cell, dir) in doors {
+                self.link(cell, dir);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{MazeGenerator, WilsonGen};
+    use crate::maze::Mask;
+    use rand::SeedableRng;
+
+    #[test]
+    fn rooms() {
+        let rng = &mut StdRng::seed_from_u64(3);
+        let room = Room {
+            level: 1,
+            x: 2,
+            y: 1,
+            width: 3,
+            height: 2,
+            doors: 2,
+        };
+        let maze = Maze::with_levels(Topology::Square, Mask::new(7, 5), 2);
+        // a room across the whole grid leaves two sides apart
+        let wall = Room {
+            level: 0,
+            x: 3,
+            y: 0,
+            width: 1,
+            height: 5,
+            ..room
+        };
+        assert!(maze.fits_rooms(&[room]));
+        assert!(!Maze::new(7, 5).fits_rooms(&[wall]));
+        let maze = maze.with_rooms(vec![room]);
+        let mut generator = WilsonGen::new(rng, maze);
+        generator.finish(rng);
+        let mut maze = generator.maze().clone();
+        let cells: Vec<_> = (0..maze.len())
+            .filter(|c| room.contains(&maze, *c))
+            .collect();
+        assert_eq!(cells.len(), 6);
+        assert!(cells.iter().all(|c| maze.links(*c).is_empty()));
+        assert!(maze.validate().is_perfect());
+
+        maze.open_rooms(rng);
+        assert!(maze.rooms().is_empty());
+        let mut doors = 0;
+        let mut inside = 0;
+        for cell in cells {
+            for n in maze.linked_neighbors(cell).iter() {
+                if room.contains(&maze, n.idx) {
+                    inside += 1;
+                } else {
+                    assert!(n.dir != Neighbor::Up && n.dir != Neighbor::Down);
+                    doors += 1;
+                }
+            }
+        }
+        // both sides of the 7 walls inside the room
+        assert_eq!(inside, 14);
+        assert_eq!(doors, 2);
+        let report = maze.validate();
+        assert!(report
+            .violations
+            .iter()
+            .all(|v| matches!(v, crate::maze::Violation::Cycle { .. })));
+    }
+}
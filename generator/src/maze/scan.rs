@@ -0,0 +1,233 @@
+use super::mask::dark_pixels;
+use super::{Maze, Neighbor};
+use crate::grid::Grid;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum ScanError {
+    Io(io::Error),
+    Png(png::DecodingError),
+    /// no dark pixels, so no walls
+    Empty,
+    /// the walls do not line up on a grid of square cells, or cells have something drawn in
+    /// them. The size is the one of the drawing in pixels, without the margins around it
+    NoGrid {
+        width: usize,
+        height: usize,
+    },
+}
+
+impl From<io::Error> for ScanError {
+    fn from(e: io::Error) -> Self {
+        ScanError::Io(e)
+    }
+}
+
+impl From<png::DecodingError> for ScanError {
+    fn from(e: png::DecodingError) -> Self {
+        ScanError::Png(e)
+    }
+}
+
+/// where walls and passages are sampled, away from the ends of walls and the corners of cells
+const SAMPLES: [f32; 3] = [0.25, 0.5, 0.75];
+
+/// Dark pixels inside the bounding box of the walls
+struct Drawing {
+    dark: Vec<bool>,
+    stride: usize,
+    left: usize,
+    top: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Drawing {
+    fn new(width: usize, height: usize, dark: Vec<bool>) -> Option<Self> {
+        let column = |x: usize| (0..height).any(|y| dark[y * width + x]);
+        let row = |y: usize| dark[y * width..(y + 1) * width].iter().any(|d| *d);
+        let left = (0..width).find(|x| column(*x))?;
+        let right = (0..width).rev().find(|x| column(*x))?;
+        let top = (0..height).find(|y| row(*y))?;
+        let bottom = (0..height).rev().find(|y| row(*y))?;
+        Some(Self {
+            dark,
+            stride: width,
+            left,
+            top,
+            width: right - left + 1,
+            height: bottom - top + 1,
+        })
+    }
+
+    fn is_dark(&self, x: f32, y: f32) -> bool {
+        let (x, y) = (x as usize, y as usize);
+        self.dark[(self.top + y) * self.stride + self.left + x]
+    }
+
+    /// Whether every pixel between `from` and `to` is light, a pixel away from the edges so that
+    /// walls rounded either way do not count
+    fn is_empty(&self, from: (f32, f32), to: (f32, f32)) -> bool {
+        let columns = (from.0 + 1.0).ceil() as usize..(to.0 - 1.0).floor().max(0.0) as usize;
+        let rows = (from.1 + 1.0).ceil() as usize..(to.1 - 1.0).floor().max(0.0) as usize;
+        rows.into_iter().all(|y| {
+            let row = (self.top + y) * self.stride + self.left;
+            columns.clone().all(|x| !self.dark[row + x])
+        })
+    }
+
+    /// Rows along the top and columns along the left that are mostly dark, the outer wall. Half
+    /// of them can be missing for the entrances of printed mazes
+    fn thickness(&self) -> usize {
+        let rows = (0..self.height)
+            .take_while(|y| {
+                let dark = (0..self.width).filter(|x| self.is_dark(*x as f32, *y as f32));
+                dark.count() * 2 >= self.width
+            })
+            .count();
+        let columns = (0..self.width)
+            .take_while(|x| {
+                let dark = (0..self.height).filter(|y| self.is_dark(*x as f32, *y as f32));
+                dark.count() * 2 >= self.height
+            })
+            .count();
+        rows.min(columns)
+    }
+
+    /// Reads the maze assuming `columns` cells a row: the rows follow from square cells, then
+    /// every cell must be empty and every wall between cells either fully drawn or missing.
+    /// Sampling a few points is not enough for cells, they would miss the walls crossing a cell
+    /// too large
+    fn read(&self, thickness: f32, columns: usize) -> Option<Maze> {
+        let cell_width = (self.width as f32 - thickness) / columns as f32;
+        let rows = ((self.height as f32 - thickness) / cell_width).round();
+        // scans are rarely exactly square
+        let cell_height = (self.height as f32 - thickness) / rows;
+        if rows < 1.0 || (cell_height - cell_width).abs() > cell_width * 0.1 {
+            return None;
+        }
+        // wall pixels on the side, passage pixels on the other
+        let (inner_width, inner_height) = (cell_width - thickness, cell_height - thickness);
+        let mut maze = Maze::new(columns, rows as usize);
+        for y in 0..rows as usize {
+            for x in 0..columns {
+                let left = x as f32 * cell_width + thickness;
+                let top = y as f32 * cell_height + thickness;
+                if !self.is_empty((left, top), (left + inner_width, top + inner_height)) {
+                    return None;
+                }
+                let cell = y * columns + x;
+                let west: Vec<_> = SAMPLES
+                    .iter()
+                    .map(|f| self.is_dark(left - thickness / 2.0, top + inner_height * f))
+                    .collect();
+                let north: Vec<_> = SAMPLES
+                    .iter()
+                    .map(|f| self.is_dark(left + inner_width * f, top - thickness / 2.0))
+                    .collect();
+                // the outer wall is closed, its gaps are entrances
+                let walls = [
+                    (x > 0, Neighbor::West, west),
+                    (y > 0, Neighbor::North, north),
+                ];
+                for (inside, dir, wall) in walls.iter() {
+                    if !inside || wall.iter().all(|d| *d) {
+                        continue;
+                    }
+                    if wall.iter().any(|d| *d) {
+                        return None;
+                    }
+                    maze.link(cell, *dir);
+                }
+            }
+        }
+        Some(maze)
+    }
+}
+
+impl Maze {
+    /// see `from_png`
+    pub fn load_png(path: impl AsRef<Path>) -> Result<Self, ScanError> {
+        Self::from_png(BufReader::new(File::open(path)?))
+    }
+
+    /// Reads a flat square maze drawn with dark walls on a light background, like the ones of
+    /// `Raster`. The grid is the one with the largest square cells that fits the walls, so a
+    /// maze whose walls were all removed is read as a single cell
+    pub fn from_png(reader: impl Read) -> Result<Self, ScanError> {
+        let (width, height, dark) = dark_pixels(reader)?;
+        let drawing = Drawing::new(width, height, dark).ok_or(ScanError::Empty)?;
+        let thickness = drawing.thickness().max(1) as f32;
+        let columns =
+            (1..).take_while(|c| (drawing.width as f32 - thickness) / *c as f32 >= thickness + 2.0);
+        for columns in columns {
+            if let Some(maze) = drawing.read(thickness, columns) {
+                return Ok(maze);
+            }
+        }
+        Err(ScanError::NoGrid {
+            width: drawing.width,
+            height: drawing.height,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{MazeGenerator, RecurBacktrackerGen};
+    use crate::raster::{Image, Raster};
+    use host_api::Color;
+    use rand::{prelude::StdRng, SeedableRng};
+
+    fn png(image: &Image) -> Vec<u8> {
+        let mut png = vec![];
+        image.write_png(&mut png).unwrap();
+        png
+    }
+
+    #[test]
+    fn scans() {
+        let white = Color {
+            r: 255,
+            g: 255,
+            b: 255,
+        };
+        let black = Color { r: 0, g: 0, b: 0 };
+        // odd sizes have walls between the samples of a single cell
+        for (width, height) in [(7, 4), (3, 3), (5, 5)].iter().cloned() {
+            let mut generator = RecurBacktrackerGen::new(Maze::new(width, height));
+            generator.finish(&mut StdRng::seed_from_u64(4));
+            let maze = generator.maze();
+            for cell_size in [10.0, 23.0, 40.0].iter() {
+                let image = Raster::new(maze)
+                    .cell_size(*cell_size)
+                    .colors(white, black)
+                    .draw();
+                let scanned = Maze::from_png(png(&image).as_slice()).unwrap();
+                assert!(&scanned == maze, "{}x{} {}", width, height, cell_size);
+            }
+        }
+
+        let blank = Image::new(20, 10, white);
+        assert!(matches!(
+            Maze::from_png(png(&blank).as_slice()),
+            Err(ScanError::Empty)
+        ));
+        // a dark square fills the cell
+        let blob = Image::new(20, 10, black);
+        assert!(matches!(
+            Maze::from_png(png(&blob).as_slice()),
+            Err(ScanError::NoGrid {
+                width: 20,
+                height: 10
+            })
+        ));
+        assert!(matches!(
+            Maze::from_png(&b"not a png"[..]),
+            Err(ScanError::Png(_))
+        ));
+    }
+}
@@ -0,0 +1,215 @@
+use super::{Cell, Links, Mask, Maze, Neighbor, Room, Topology, Wrap};
+
+/// Rotations and reflections of square grids, rotations are clockwise
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Transform {
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// east and west swap
+    MirrorHorizontal,
+    /// north and south swap
+    MirrorVertical,
+    /// rows become columns, north and west swap
+    Transpose,
+    /// transpose along the other diagonal, north and east swap
+    AntiTranspose,
+}
+
+impl Transform {
+    /// every symmetry of a square, but the identity
+    pub const ALL: [Transform; 7] = [
+        Transform::Rotate90,
+        Transform::Rotate180,
+        Transform::Rotate270,
+        Transform::MirrorHorizontal,
+        Transform::MirrorVertical,
+        Transform::Transpose,
+        Transform::AntiTranspose,
+    ];
+
+    /// rows and columns swap
+    fn swaps_axes(self) -> bool {
+        matches!(
+            self,
+            Transform::Rotate90
+                | Transform::Rotate270
+                | Transform::Transpose
+                | Transform::AntiTranspose
+        )
+    }
+
+    /// where `(x, y)` ends up on a `width` x `height` grid
+    fn position(self, (x, y): (usize, usize), width: usize, height: usize) -> (usize, usize) {
+        match self {
+            Transform::Rotate90 => (height - 1 - y, x),
+            Transform::Rotate180 => (width - 1 - x, height - 1 - y),
+            Transform::Rotate270 => (y, width - 1 - x),
+            Transform::MirrorHorizontal => (width - 1 - x, y),
+            Transform::MirrorVertical => (x, height - 1 - y),
+            Transform::Transpose => (y, x),
+            Transform::AntiTranspose => (height - 1 - y, width - 1 - x),
+        }
+    }
+
+    fn direction(self, dir: Neighbor) -> Neighbor {
+        use Neighbor::*;
+        let (north, south, east, west) = match self {
+            Transform::Rotate90 => (East, West, South, North),
+            Transform::Rotate180 => (South, North, West, East),
+            Transform::Rotate270 => (West, East, North, South),
+            Transform::MirrorHorizontal => (North, South, West, East),
+            Transform::MirrorVertical => (South, North, East, West),
+            Transform::Transpose => (West, East, South, North),
+            Transform::AntiTranspose => (East, West, North, South),
+        };
+        match dir {
+            North => north,
+            South => south,
+            East => east,
+            West => west,
+            dir => dir,
+        }
+    }
+
+    fn links(self, maze: &Maze, links: Links) -> Links {
+        let mut cell = Cell::default();
+        for dir in maze.directions().filter(|d| links.has(*d)) {
+            cell.link(self.direction(dir));
+        }
+        cell.links()
+    }
+}
+
+impl Maze {
+    /// A copy of the maze, rotated or mirrored along with its mask, levels, tunnels and rooms.
+    /// Only square grids can be transformed, and cylinders only wrap east to west so they cannot
+    /// be turned on their side
+    pub fn transformed(&self, transform: Transform) -> Option<Self> {
+        let square = matches!(self.topology, Topology::Square | Topology::Weave);
+        if !square || (self.wrap == Wrap::Cylinder && transform.swaps_axes()) {
+            return None;
+        }
+        let (width, height) = (self.width, self.height());
+        let (new_width, new_height) = if transform.swaps_axes() {
+            (height, width)
+        } else {
+            (width, height)
+        };
+        let level_len = self.mask.len();
+        let moved = |cell: usize| {
+            let (x, y) = (cell % level_len % width, cell % level_len / width);
+            let (x, y) = transform.position((x, y), width, height);
+            cell / level_len * level_len + y * new_width + x
+        };
+
+        let mut mask = Mask::new(new_width, new_height);
+        for cell in (0..level_len).filter(|c| !self.mask.is_enabled(*c)) {
+            mask.disable(moved(cell));
+        }
+        let mut cells = vec![Cell::default(); self.cells.len()];
+        for (cell, old) in self.cells.iter().enumerate() {
+            let links = transform.links(self, old.links());
+            // under cells keep their place after the rest
+            let cell = if cell < self.grid_len() {
+                moved(cell)
+            } else {
+                cell
+            };
+            cells[cell] = Cell { links };
+        }
+        let rooms = self
+            .rooms
+            .iter()
+            .map(|room| {
+                let a = transform.position((room.x, room.y), width, height);
+                let far = (room.x + room.width - 1, room.y + room.height - 1);
+                let b = transform.position(far, width, height);
+                Room {
+                    x: a.0.min(b.0),
+                    y: a.1.min(b.1),
+                    width: a.0.max(b.0) - a.0.min(b.0) + 1,
+                    height: a.1.max(b.1) - a.1.min(b.1) + 1,
+                    ..*room
+                }
+            })
+            .collect();
+        let maze = Self {
+            cells,
+            width: new_width,
+            mask,
+            topology: self.topology,
+            polar: None,
+            levels: self.levels,
+            wrap: self.wrap,
+            unders: self.unders.iter().map(|over| moved(*over)).collect(),
+            rooms: vec![],
+            in_rooms: vec![],
+        };
+        Some(maze.with_rooms(rooms))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{BinaryTreeGen, MazeGenerator, RecurBacktrackerGen};
+    use rand::{prelude::StdRng, SeedableRng};
+
+    fn inverse(transform: Transform) -> Transform {
+        match transform {
+            Transform::Rotate90 => Transform::Rotate270,
+            Transform::Rotate270 => Transform::Rotate90,
+            t => t,
+        }
+    }
+
+    #[test]
+    fn transforms() {
+        let rng = &mut StdRng::seed_from_u64(5);
+        // binary trees only stay perfect without masked cells along the north and east edges
+        let mut mask = Mask::new(5, 3);
+        mask.disable(10);
+        let mut binary_tree = BinaryTreeGen::new(Maze::with_mask(mask));
+        binary_tree.finish(rng);
+        let mut weave =
+            RecurBacktrackerGen::new(Maze::with_topology(Topology::Weave, Mask::new(7, 5)));
+        weave.finish(rng);
+        let mut levels =
+            RecurBacktrackerGen::new(Maze::with_levels(Topology::Square, Mask::new(4, 3), 2));
+        levels.finish(rng);
+        let mazes = [binary_tree.maze(), weave.maze(), levels.maze()];
+        for maze in mazes.iter().cloned() {
+            for transform in Transform::ALL.iter().cloned() {
+                let transformed = maze.transformed(transform).unwrap();
+                assert!(transformed.validate().is_perfect(), "{:?}", transform);
+                let back = transformed.transformed(inverse(transform)).unwrap();
+                assert!(&back == maze, "{:?}", transform);
+            }
+        }
+
+        let maze = Maze::from_ascii(
+            "+---+---+---+\n\
+             |           |\n\
+             +   +---+   +\n\
+             |   |       |\n\
+             +---+---+---+\n",
+        )
+        .unwrap();
+        let rotated = maze.transformed(Transform::Rotate90).unwrap();
+        assert_eq!(
+            rotated.to_ascii(),
+            "+---+---+\n\
+             |       |\n\
+             +---+   +\n\
+             |   |   |\n\
+             +   +   +\n\
+             |       |\n\
+             +---+---+\n"
+        );
+        let cylinder = Maze::new(4, 3).wrapped(Wrap::Cylinder);
+        assert!(cylinder.transformed(Transform::Transpose).is_none());
+        assert!(cylinder.transformed(Transform::MirrorVertical).is_some());
+        assert!(Maze::polar(3).transformed(Transform::Rotate180).is_none());
+    }
+}
@@ -0,0 +1,64 @@
+use super::{Maze, Neighbor};
+
+impl Maze {
+    /// Triangles alternate between pointing up and down, so every cell has a neighbor on each side
+    /// and only one of north (pointing down) or south (pointing up)
+    pub(super) fn triangle_neighbor_at(&self, cell: usize, n: Neighbor) -> Option<usize> {
+        let row = cell / self.width;
+        let col = cell % self.width;
+        match n {
+            Neighbor::West if col > 0 => Some(cell - 1),
+            Neighbor::East if col + 1 < self.width => Some(cell + 1),
+            Neighbor::North if !self.is_upright(cell) && row > 0 => Some(cell - self.width),
+            Neighbor::South if self.is_upright(cell) && row + 1 < self.height() => {
+                Some(cell + self.width)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_upright(&self, cell: usize) -> bool {
+        let cell = self.level_cell(cell);
+        let row = cell / self.width;
+        let col = cell % self.width;
+        (row + col) % 2 == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+    use crate::maze::{Mask, Topology};
+
+    #[test]
+    fn neighbor_at() {
+        let mut maze = Maze::with_topology(Topology::Triangle, Mask::new(4, 2));
+        let at = |maze: &Maze, cell, dir| maze.neighbor_at(cell, dir).map(|n| n.idx);
+        assert!(maze.is_upright(0) && !maze.is_upright(1) && !maze.is_upright(4));
+        // pointing up, the neighbor is below
+        assert_eq!(at(&maze, 0, Neighbor::South), Some(4));
+        assert_eq!(at(&maze, 0, Neighbor::North), None);
+        assert_eq!(at(&maze, 0, Neighbor::West), None);
+        assert_eq!(at(&maze, 0, Neighbor::East), Some(1));
+        assert_eq!(at(&maze, 2, Neighbor::South), Some(6));
+        assert_eq!(at(&maze, 5, Neighbor::South), None);
+        assert_eq!(at(&maze, 5, Neighbor::North), None);
+        // pointing down, the neighbor is above
+        assert_eq!(at(&maze, 1, Neighbor::North), None);
+        assert_eq!(at(&maze, 1, Neighbor::South), None);
+        assert_eq!(at(&maze, 4, Neighbor::North), Some(0));
+        assert_eq!(at(&maze, 6, Neighbor::North), Some(2));
+        assert_eq!(at(&maze, 7, Neighbor::East), None);
+
+        maze.link(0, Neighbor::South);
+        let back: Vec<_> = maze
+            .linked_neighbors(4)
+            .iter()
+            .map(|n| (n.dir, n.idx))
+            .collect();
+        assert_eq!(back, vec![(Neighbor::North, 0)]);
+        maze.unlink(4, Neighbor::North);
+        assert!(maze.links(0).is_empty() && maze.links(4).is_empty());
+    }
+}
@@ -0,0 +1,128 @@
+use super::{Maze, Neighbor};
+use crate::grid::Grid;
+use std::collections::VecDeque;
+
+/// Everything that keeps a maze from being perfect, see `Maze::validate`
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Report {
+    pub violations: Vec<Violation>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Violation {
+    /// `cell` links towards `dir`, but there is no cell there: it is off the grid or masked
+    OffGrid { cell: usize, dir: Neighbor },
+    /// `cell` links to `neighbor`, which does not link back
+    Asymmetric {
+        cell: usize,
+        dir: Neighbor,
+        neighbor: usize,
+    },
+    /// the cell cannot be reached from the first enabled cell
+    Unreachable { cell: usize },
+    /// the link between both cells closes a loop
+    Cycle { cell: usize, neighbor: usize },
+}
+
+impl Report {
+    /// links are consistent and there are no loops, but some cells may not be linked yet
+    pub fn is_valid(&self) -> bool {
+        self.violations
+            .iter()
+            .all(|v| matches!(v, Violation::Unreachable { .. }))
+    }
+
+    /// every cell can be reached through a single path
+    pub fn is_perfect(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl Maze {
+    /// Checks the links of every cell, unlike `linked_neighbors` which skips broken ones
+    pub fn validate(&self) -> Report {
+        let mut violations = vec![];
+        for cell in 0..self.len() {
+            for dir in self.directions() {
+                if !self.cells[cell].has_link(dir) {
+                    continue;
+                }
+                match self.neighbor_at(cell, dir) {
+                    None => violations.push(Violation::OffGrid { cell, dir }),
+                    Some(n) if self.linked_neighbor_at(cell, dir).is_none() => {
+                        violations.push(Violation::Asymmetric {
+                            cell,
+                            dir,
+                            neighbor: n.idx,
+                        })
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+
+        // walk every group of linked cells, the first one must hold every enabled cell
+        let mut parents: Vec<Option<usize>> = vec![None; self.len()];
+        let mut visited = vec![false; self.len()];
+        let mut pending = VecDeque::new();
+        for root in (0..self.len()).filter(|c| self.is_enabled(*c)) {
+            if visited[root] {
+                continue;
+            }
+            let reachable = self.first_enabled() == Some(root);
+            visited[root] = true;
+            pending.push_back(root);
+            while let Some(cell) = pending.pop_front() {
+                if !reachable {
+                    violations.push(Violation::Unreachable { cell });
+                }
+                for n in self.linked_neighbors(cell).iter() {
+                    if parents[cell] == Some(n.idx) {
+                        continue;
+                    }
+                    if !visited[n.idx] {
+                        visited[n.idx] = true;
+                        parents[n.idx] = Some(cell);
+                        pending.push_back(n.idx);
+                    } else if cell < n.idx {
+                        // seen from both ends, only report it once
+                        violations.push(Violation::Cycle {
+                            cell,
+                            neighbor: n.idx,
+                        });
+                    }
+                }
+            }
+        }
+        Report { violations }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn broken_links() {
+        let mut maze = Maze::new(2, 2);
+        maze.link(0, Neighbor::East);
+        maze.link(0, Neighbor::South);
+        maze.link(1, Neighbor::South);
+        maze.cells[3].unlink(Neighbor::North);
+        maze.cells[3].link(Neighbor::East);
+        let report = maze.validate();
+        assert!(!report.is_valid());
+        assert!(report.violations.contains(&Violation::Asymmetric {
+            cell: 1,
+            dir: Neighbor::South,
+            neighbor: 3,
+        }));
+        assert!(report.violations.contains(&Violation::OffGrid {
+            cell: 3,
+            dir: Neighbor::East,
+        }));
+        assert!(report
+            .violations
+            .contains(&Violation::Unreachable { cell: 3 }));
+    }
+}
@@ -0,0 +1,94 @@
+use super::{Cell, Maze, Neighbor, Neighbor1, Topology};
+use crate::grid::Grid;
+
+impl Maze {
+    /// Under cells only have neighbors along their tunnel
+    pub(super) fn under_neighbor_at(
+        &self,
+        under: usize,
+        over: usize,
+        n: Neighbor,
+    ) -> Option<usize> {
+        if self.cells[under].has_link(n) {
+            self.neighbor_at(over, n).map(|n| n.idx)
+        } else {
+            None
+        }
+    }
+
+    /// the cell a tunnel passes under, if `cell` is an under cell
+    pub fn over_cell(&self, cell: usize) -> Option<usize> {
+        cell.checked_sub(self.grid_len())
+            .map(|under| self.unders[under])
+    }
+
+    /// the under cell of the tunnel that passes under `cell`, if any
+    pub(super) fn under_cell(&self, cell: usize) -> Option<usize> {
+        self.unders
+            .iter()
+            .rposition(|over| *over == cell)
+            .map(|under| self.grid_len() + under)
+    }
+
+    /// Cells two steps away that can be reached by tunneling under a perpendicular corridor
+    pub(super) fn weave_tunnel_neighbors(&self, cell: usize) -> Vec<Neighbor1> {
+        if self.topology != Topology::Weave || self.over_cell(cell).is_some() {
+            return vec![];
+        }
+        self.topology
+            .directions()
+            .iter()
+            .filter_map(|dir| {
+                let middle = self.neighbor_at(cell, *dir)?;
+                let far = self.neighbor_at(middle.idx, *dir)?;
+                if self.can_tunnel_under(middle.idx, *dir) {
+                    Some(Neighbor1 {
+                        dir: *dir,
+                        idx: far.idx,
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// `cell` is a straight corridor perpendicular to `dir`, without a tunnel under it yet
+    fn can_tunnel_under(&self, cell: usize, dir: Neighbor) -> bool {
+        let (along, across) = match dir {
+            Neighbor::North | Neighbor::South => (Neighbor::North, Neighbor::East),
+            _ => (Neighbor::East, Neighbor::North),
+        };
+        let links = self.cells[cell].links();
+        links.has(across)
+            && links.has(across.opposite())
+            && !links.has(along)
+            && !links.has(along.opposite())
+            && self.under_cell(cell).is_none()
+    }
+
+    /// Links `cell` with the cell two steps away in `dir`, through a new under cell
+    pub(super) fn weave_tunnel(&mut self, cell: usize, dir: Neighbor) {
+        let middle = self.neighbor_at(cell, dir).unwrap().idx;
+        let far = self.neighbor_at(middle, dir).unwrap().idx;
+        let mut under = Cell::default();
+        under.link(dir);
+        under.link(dir.opposite());
+        self.cells.push(under);
+        self.unders.push(middle);
+        self.cells[cell].link(dir);
+        self.cells[far].link(dir.opposite());
+    }
+
+    /// Undoes `weave_tunnel`. Tunnels must be undone in the reverse order they were created
+    pub(super) fn weave_untunnel(&mut self, cell: usize, dir: Neighbor) {
+        let middle = self.neighbor_at(cell, dir).unwrap().idx;
+        let far = self.neighbor_at(middle, dir).unwrap().idx;
+        let under = self.under_cell(middle).expect("no tunnel to undo");
+        assert_eq!(under, self.cells.len() - 1, "tunnels undone out of order");
+        self.cells.pop();
+        self.unders.pop();
+        self.cells[cell].unlink(dir);
+        self.cells[far].unlink(dir.opposite());
+    }
+}
@@ -0,0 +1,89 @@
+use super::{Maze, Neighbor, Topology};
+use crate::grid::Grid;
+use serde::{Deserialize, Serialize};
+
+/// Edges of a square grid that lead to the opposite edge
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum Wrap {
+    None,
+    /// the east and west edges
+    Cylinder,
+    /// every edge
+    Torus,
+}
+
+impl Maze {
+    /// Only square grids wrap. Grids less than 3 cells wide (or high) do not wrap that way, the
+    /// cells on both edges would be neighbors twice
+    pub fn wrapped(mut self, wrap: Wrap) -> Self {
+        assert!(
+            wrap == Wrap::None || self.topology == Topology::Square,
+            "only square grids wrap"
+        );
+        self.wrap = wrap;
+        self
+    }
+
+    pub(super) fn wraps_east_west(&self) -> bool {
+        self.wrap != Wrap::None && self.width > 2
+    }
+
+    pub(super) fn wraps_north_south(&self) -> bool {
+        self.wrap == Wrap::Torus && self.height() > 2
+    }
+
+    /// the neighbor in `dir` is on the opposite edge of the grid
+    pub fn is_wrapped(&self, cell: usize, dir: Neighbor) -> bool {
+        if self.wrap == Wrap::None || self.neighbor_at(cell, dir).is_none() {
+            return false;
+        }
+        let cell = self.level_cell(cell);
+        let (x, y) = (cell % self.width, cell / self.width);
+        match dir {
+            Neighbor::North => y == 0,
+            Neighbor::South => y + 1 == self.height(),
+            Neighbor::East => x + 1 == self.width,
+            Neighbor::West => x == 0,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neighbor_at() {
+        let at = |maze: &Maze, cell, dir| maze.neighbor_at(cell, dir).map(|n| n.idx);
+        let mut cylinder = Maze::new(3, 3).wrapped(Wrap::Cylinder);
+        assert_eq!(at(&cylinder, 0, Neighbor::West), Some(2));
+        assert_eq!(at(&cylinder, 2, Neighbor::East), Some(0));
+        assert_eq!(at(&cylinder, 0, Neighbor::North), None);
+        assert_eq!(at(&cylinder, 6, Neighbor::South), None);
+        assert!(cylinder.is_wrapped(0, Neighbor::West));
+        assert!(!cylinder.is_wrapped(0, Neighbor::East));
+        assert!(cylinder.unwrapped_neighbor_at(0, Neighbor::West).is_none());
+
+        cylinder.link(0, Neighbor::West);
+        let back: Vec<_> = cylinder
+            .linked_neighbors(2)
+            .iter()
+            .map(|n| (n.dir, n.idx))
+            .collect();
+        assert_eq!(back, vec![(Neighbor::East, 0)]);
+        cylinder.unlink(2, Neighbor::East);
+        assert!(cylinder.links(0).is_empty() && cylinder.links(2).is_empty());
+
+        let torus = Maze::new(3, 3).wrapped(Wrap::Torus);
+        assert_eq!(at(&torus, 0, Neighbor::North), Some(6));
+        assert_eq!(at(&torus, 6, Neighbor::South), Some(0));
+        assert_eq!(at(&torus, 8, Neighbor::East), Some(6));
+        assert!(torus.is_wrapped(6, Neighbor::South));
+        assert!(!torus.is_wrapped(4, Neighbor::South));
+        // the cells on both edges would be neighbors twice
+        let narrow = Maze::new(2, 2).wrapped(Wrap::Torus);
+        assert_eq!(at(&narrow, 0, Neighbor::West), None);
+        assert_eq!(at(&narrow, 0, Neighbor::North), None);
+    }
+}
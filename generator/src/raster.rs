@@ -0,0 +1,376 @@
+use crate::grid::Grid;
+use crate::maze::Maze;
+use crate::render::{self, TILE_WIDTH};
+use crate::Overlay;
+use host_api::{Color, RenderCommand, RenderGroup};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// RGBA pixels, row by row from the top left corner
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, background: Color) -> Self {
+        let pixels = [background.r, background.g, background.b, 255]
+            .iter()
+            .cloned()
+            .cycle()
+            .take(width * height * 4)
+            .collect();
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write_png(&mut file)?;
+        file.flush()
+    }
+
+    pub fn write_png(&self, w: &mut impl Write) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(w, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::RGBA);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        Ok(())
+    }
+
+    /// Fills the pixels whose center is inside the polygon, even-odd. No antialiasing, so the
+    /// same maze always gives the same pixels
+    fn fill_polygon(&mut self, points: &[(f32, f32)], color: Color) {
+        let (top, bottom) = points
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(top, bottom), p| {
+                (top.min(p.1), bottom.max(p.1))
+            });
+        let first = (top - 0.5).ceil().max(0.0) as usize;
+        let last = ((bottom - 0.5).ceil().max(0.0) as usize).min(self.height);
+        let mut crossings = vec![];
+        for y in first..last {
+            let center = y as f32 + 0.5;
+            crossings.clear();
+            for (i, a) in points.iter().enumerate() {
+                let b = points[(i + 1) % points.len()];
+                if (a.1 <= center) != (b.1 <= center) {
+                    crossings.push(a.0 + (center - a.1) * (b.0 - a.0) / (b.1 - a.1));
+                }
+            }
+            crossings.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            for span in crossings.chunks_exact(2) {
+                let from = (span[0] - 0.5).ceil().max(0.0) as usize;
+                let to = ((span[1] - 0.5).ceil().max(0.0) as usize).min(self.width);
+                for x in from..to {
+                    let i = (y * self.width + x) * 4;
+                    self.pixels[i..i + 4].copy_from_slice(&[color.r, color.g, color.b, 255]);
+                }
+            }
+        }
+    }
+
+    /// a quad around the line, never thinner than a pixel so it cannot vanish
+    fn line(&mut self, from: (f32, f32), to: (f32, f32), thickness: f32, color: Color) {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length < f32::EPSILON {
+            return;
+        }
+        let half = thickness.max(1.0) / 2.0;
+        let (nx, ny) = (-dy / length * half, dx / length * half);
+        let quad = [
+            (from.0 + nx, from.1 + ny),
+            (to.0 + nx, to.1 + ny),
+            (to.0 - nx, to.1 - ny),
+            (from.0 - nx, from.1 - ny),
+        ];
+        self.fill_polygon(&quad, color);
+    }
+
+    fn rectangle(&mut self, (x, y): (f32, f32), width: f32, height: f32, color: Color) {
+        let (width, height) = (width.max(1.0), height.max(1.0));
+        let corners = [
+            (x, y),
+            (x + width, y),
+            (x + width, y + height),
+            (x, y + height),
+        ];
+        self.fill_polygon(&corners, color);
+    }
+}
+
+// for golden images, nothing in the app reads pixels back
+#[cfg(test)]
+impl Image {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Color {
+        let i = (y * self.width + x) * 4;
+        Color {
+            r: self.pixels[i],
+            g: self.pixels[i + 1],
+            b: self.pixels[i + 2],
+        }
+    }
+}
+
+/// Draws mazes on the CPU the way the engine draws them, for thumbnails and golden images
+/// where there is no window nor GPU
+pub struct Raster<'a> {
+    maze: &'a Maze,
+    level: usize,
+    /// pixels per tile
+    cell_size: f32,
+    background: Color,
+    borders: Color,
+    overlay: Option<(Overlay, &'a [usize], &'a [usize])>,
+}
+
+/// empty pixels around the maze
+const MARGIN: f32 = 2.0;
+
+impl<'a> Raster<'a> {
+    pub fn new(maze: &'a Maze) -> Self {
+        Self {
+            maze,
+            level: 0,
+            cell_size: 20.0,
+            background: Color { r: 0, g: 0, b: 0 },
+            borders: Color {
+                r: 117,
+                g: 140,
+                b: 140,
+            },
+            overlay: None,
+        }
+    }
+
+    pub fn level(mut self, level: usize) -> Self {
+        self.level = level;
+        self
+    }
+
+    pub fn cell_size(mut self, cell_size: f32) -> Self {
+        self.cell_size = cell_size;
+        self
+    }
+
+    pub fn colors(mut self, background: Color, borders: Color) -> Self {
+        self.background = background;
+        self.borders = borders;
+        self
+    }
+
+    /// see `render::render_overlay`
+    pub fn overlay(mut self, overlay: Overlay, distances: &'a [usize], path: &'a [usize]) -> Self {
+        self.overlay = Some((overlay, distances, path));
+        self
+    }
+
+    pub fn draw(&self) -> Image {
+        let maze = self.maze;
+        let mut group = RenderGroup::default();
+        if let Some((overlay, distances, path)) = self.overlay {
+            render::render_overlay(&mut group, maze, self.level, overlay, distances, path);
+        }
+        for cell in 0..maze.len() {
+            render::render_borders(&mut group, maze, self.level, cell, self.borders);
+        }
+        let commands: Vec<_> = group.drain().collect();
+
+        let (mut min, mut max) = ((f32::MAX, f32::MAX), (f32::MIN, f32::MIN));
+        for (x, y) in commands.iter().flat_map(outline) {
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+        if min.0 > max.0 {
+            min = (0.0, 0.0);
+            max = (0.0, 0.0);
+        }
+        let scale = self.cell_size / TILE_WIDTH;
+        let size = |from: f32, to: f32| ((to - from) * scale + 2.0 * MARGIN).ceil() as usize;
+        let mut image = Image::new(size(min.0, max.0), size(min.1, max.1), self.background);
+        let at = |(x, y): (f32, f32)| ((x - min.0) * scale + MARGIN, (y - min.1) * scale + MARGIN);
+        for command in commands {
+            match command {
+                RenderCommand::FillRectangle {
+                    x,
+                    y,
+                    width,
+                    height,
+                    color,
+                } => image.rectangle(at((x, y)), width * scale, height * scale, color),
+                RenderCommand::DrawRectangle {
+                    x,
+                    y,
+                    width,
+                    height,
+                    color,
+                } => {
+                    let corners = [
+                        (x, y),
+                        (x + width, y),
+                        (x + width, y + height),
+                        (x, y + height),
+                    ];
+                    for i in 0..corners.len() {
+                        let (a, b) = (corners[i], corners[(i + 1) % corners.len()]);
+                        image.line(at(a), at(b), 1.0, color);
+                    }
+                }
+                RenderCommand::Line {
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    thickness,
+                    color,
+                } => image.line(at((x1, y1)), at((x2, y2)), thickness * scale, color),
+                RenderCommand::Arc {
+                    x,
+                    y,
+                    radius,
+                    start,
+                    end,
+                    thickness,
+                    color,
+                } => {
+                    let points = arc_points((x, y), radius, start, end);
+                    for pair in points.windows(2) {
+                        image.line(at(pair[0]), at(pair[1]), thickness * scale, color);
+                    }
+                }
+                RenderCommand::FillPolygon { points, color } => {
+                    let points: Vec<_> = points.into_iter().map(at).collect();
+                    image.fill_polygon(&points, color);
+                }
+                RenderCommand::Text { .. } | RenderCommand::Camera { .. } => {}
+            }
+        }
+        image
+    }
+}
+
+/// points along an arc, one every few units like the engine does
+fn arc_points((x, y): (f32, f32), radius: f32, start: f32, end: f32) -> Vec<(f32, f32)> {
+    let segments = ((end - start) * radius / 4.0).ceil().max(1.0) as usize;
+    (0..=segments)
+        .map(|i| {
+            let angle = start + (end - start) * i as f32 / segments as f32;
+            (x + radius * angle.cos(), y + radius * angle.sin())
+        })
+        .collect()
+}
+
+/// points around what a command draws, to size the image
+fn outline(command: &RenderCommand) -> Vec<(f32, f32)> {
+    match command {
+        RenderCommand::FillRectangle {
+            x,
+            y,
+            width,
+            height,
+            ..
+        }
+        | RenderCommand::DrawRectangle {
+            x,
+            y,
+            width,
+            height,
+            ..
+        } => vec![(*x, *y), (x + width, y + height)],
+        RenderCommand::Line { x1, y1, x2, y2, .. } => vec![(*x1, *y1), (*x2, *y2)],
+        RenderCommand::Arc {
+            x,
+            y,
+            radius,
+            start,
+            end,
+            ..
+        } => arc_points((*x, *y), *radius, *start, *end),
+        RenderCommand::FillPolygon { points, .. } => points.clone(),
+        RenderCommand::Text { .. } | RenderCommand::Camera { .. } => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra;
+    use crate::maze::{Neighbor, Topology};
+
+    #[test]
+    fn draw() {
+        let mut maze = Maze::new(2, 1);
+        maze.link(0, Neighbor::East);
+        let rgb = |c: Color| (c.r, c.g, c.b);
+        let black = Color { r: 0, g: 0, b: 0 };
+        let white = Color {
+            r: 255,
+            g: 255,
+            b: 255,
+        };
+        let image = Raster::new(&maze)
+            .cell_size(10.0)
+            .colors(black, white)
+            .draw();
+        assert_eq!((image.width(), image.height()), (24, 14));
+        // walls on the outer edge, none between the linked cells
+        assert_eq!(rgb(image.pixel(2, 7)), rgb(white));
+        assert_eq!(rgb(image.pixel(7, 2)), rgb(white));
+        assert_eq!(rgb(image.pixel(7, 7)), rgb(black));
+        assert_eq!(rgb(image.pixel(12, 7)), rgb(black));
+
+        let distances = dijkstra::flood(0, &maze);
+        let image = Raster::new(&maze)
+            .cell_size(10.0)
+            .overlay(Overlay::Distances, &distances, &[])
+            .draw();
+        assert_eq!(rgb(image.pixel(7, 7)), rgb(Color::gradient_gray(0.0)));
+        assert_eq!(rgb(image.pixel(17, 7)), rgb(Color::gradient_gray(1.0)));
+        // a single cell is the farthest from itself, and nothing reachable draws no overlay
+        let cell = Maze::new(1, 1);
+        let image = Raster::new(&cell)
+            .cell_size(10.0)
+            .overlay(Overlay::Distances, &[0], &[])
+            .draw();
+        assert_eq!(rgb(image.pixel(7, 7)), rgb(Color::gradient_gray(0.0)));
+        let image = Raster::new(&cell)
+            .overlay(Overlay::Distances, &[], &[])
+            .draw();
+        assert_eq!(rgb(image.pixel(7, 7)), rgb(black));
+
+        let mut png = vec![];
+        image.write_png(&mut png).unwrap();
+        let decoder = png::Decoder::new(png.as_slice());
+        let (info, mut reader) = decoder.read_info().unwrap();
+        let mut pixels = vec![0; info.buffer_size()];
+        reader.next_frame(&mut pixels).unwrap();
+        assert_eq!(pixels, image.pixels());
+
+        // every topology draws something
+        let polar = Maze::polar(3);
+        let hex = Maze::with_topology(Topology::Hex, crate::maze::Mask::new(3, 3));
+        for maze in [polar, hex].iter() {
+            let image = Raster::new(maze).colors(black, white).draw();
+            assert!(image.pixels().chunks(4).any(|p| p[0] == 255));
+        }
+    }
+}
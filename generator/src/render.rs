@@ -1,6 +1,9 @@
 use host_api::{Color, RenderCommand, RenderGroup};
 
-use crate::maze::Maze;
+use crate::grid::Grid;
+use crate::maze::{Maze, Neighbor, Topology};
+use crate::Overlay;
+use std::f32::consts::PI;
 
 pub const DARK_RED: Color = Color {
     r: 145,
@@ -14,7 +17,117 @@ pub const RED: Color = Color {
     b: 0,
 };
 
-pub fn render_cell(render_group: &mut RenderGroup, x: usize, y: usize, color: Color) {
+/// Grids that know how to draw their cells, one level at a time
+pub trait RenderGrid: Grid {
+    fn render_cell(&self, render_group: &mut RenderGroup, level: usize, cell: usize, color: Color);
+    fn render_borders(
+        &self,
+        render_group: &mut RenderGroup,
+        level: usize,
+        cell: usize,
+        color: Color,
+    );
+}
+
+impl RenderGrid for Maze {
+    fn render_cell(&self, render_group: &mut RenderGroup, level: usize, cell: usize, color: Color) {
+        render_cell(render_group, self, level, cell, color)
+    }
+
+    fn render_borders(
+        &self,
+        render_group: &mut RenderGroup,
+        level: usize,
+        cell: usize,
+        color: Color,
+    ) {
+        render_borders(render_group, self, level, cell, color)
+    }
+}
+
+/// Cells are only drawn when they are on `level`
+pub fn render_cell(
+    render_group: &mut RenderGroup,
+    maze: &Maze,
+    level: usize,
+    cell: usize,
+    color: Color,
+) {
+    if maze.level(cell) != level {
+        return;
+    }
+    match maze.topology() {
+        Topology::Square => {
+            let x = maze.level_cell(cell) % maze.width();
+            let y = maze.level_cell(cell) / maze.width();
+            render_square_cell(render_group, x, y, color)
+        }
+        Topology::Weave => render_weave_cell(render_group, maze, cell, color),
+        Topology::Hex => {
+            let points = hex_corners(maze, cell).to_vec();
+            render_group.push(RenderCommand::FillPolygon { points, color });
+        }
+        Topology::Triangle => {
+            let points = triangle_corners(maze, cell).to_vec();
+            render_group.push(RenderCommand::FillPolygon { points, color });
+        }
+        Topology::Polar => {
+            let sector = PolarSector::new(maze, cell);
+            let center = polar_center(maze);
+            let mut points = arc_points(center, sector.inner, sector.start, sector.end);
+            let mut outer = arc_points(center, sector.outer, sector.start, sector.end);
+            outer.reverse();
+            points.append(&mut outer);
+            render_group.push(RenderCommand::FillPolygon { points, color });
+        }
+    }
+}
+
+/// Colors the cells of the overlay, to be drawn before the borders
+pub fn render_overlay(
+    render_group: &mut RenderGroup,
+    maze: &Maze,
+    level: usize,
+    overlay: Overlay,
+    distances: &[usize],
+    longest_path: &[usize],
+) {
+    match overlay {
+        Overlay::Distances => {
+            let max_distance = match distances.iter().filter(|d| **d < maze.len()).max() {
+                Some(max) => (*max).max(1) as f64,
+                // nothing reachable
+                None => return,
+            };
+            for (cell, distance) in distances.iter().cloned().enumerate() {
+                if distance >= maze.len() {
+                    // masked or unreachable
+                    continue;
+                }
+                render_cell(
+                    render_group,
+                    maze,
+                    level,
+                    cell,
+                    Color::gradient_gray(distance as f64 / max_distance),
+                );
+                // let text = format!("d:{}", state.distances[idx]);
+                // render_cell_text(host_api.render_group(), 0.0, 0.0, text);
+
+                // if idx == (state.generation.current_step + 1) / 2 {
+                //     render_cell(host_api.render_group(), x, y, RED)
+                // }
+            }
+        }
+        Overlay::LongestPath => {
+            for cell in longest_path {
+                render_cell(render_group, maze, level, *cell, Color::gradient_gray(0.0));
+            }
+        }
+    }
+}
+
+fn render_square_cell(render_group: &mut RenderGroup, x: usize, y: usize, color: Color) {
     let command = RenderCommand::FillRectangle {
         x: x as f32 * TILE_WIDTH,
         y: y as f32 * TILE_HEIGHT,
@@ -25,8 +138,8 @@ pub fn render_cell(render_group: &mut RenderGroup, x: usize, y: usize, color: Co
     render_group.push(command);
 }
 
-const TILE_WIDTH: f32 = 100.0;
-const TILE_HEIGHT: f32 = 100.0;
+pub const TILE_WIDTH: f32 = 100.0;
+pub const TILE_HEIGHT: f32 = 100.0;
 const BORDER_WIDTH: f32 = 3.0;
 const BORDER_HEIGHT: f32 = 3.0;
 
@@ -42,16 +155,85 @@ const BORDER_HEIGHT: f32 = 3.0;
 
 pub fn render_borders(
     render_group: &mut RenderGroup,
-    x: usize,
-    y: usize,
     maze: &Maze,
+    level: usize,
+    cell: usize,
     color: Color,
 ) {
-    let idx = y * maze.width() + x;
+    if !maze.is_enabled(cell) || maze.level(cell) != level {
+        return;
+    }
+    match maze.topology() {
+        Topology::Square => render_square_borders(render_group, maze, cell, color),
+        Topology::Hex => render_hex_borders(render_group, maze, cell, color),
+        Topology::Polar => render_polar_borders(render_group, maze, cell, color),
+        Topology::Triangle => render_triangle_borders(render_group, maze, cell, color),
+        Topology::Weave => render_weave_borders(render_group, maze, cell, color),
+    }
+    if maze.is_stairs(cell) {
+        render_stairs(render_group, maze, cell, color);
+    }
+}
+
+/// an arrow pointing up and/or down in the middle of the cell
+fn render_stairs(render_group: &mut RenderGroup, maze: &Maze, cell: usize, color: Color) {
+    let (x, y) = cell_center(maze, cell);
+    let size = TILE_WIDTH / 8.0;
+    let links = maze.cells()[cell].links();
+    let arrows = [(Neighbor::Up, -1.0), (Neighbor::Down, 1.0)];
+    for (dir, sign) in arrows.iter().cloned() {
+        if !links.has(dir) {
+            continue;
+        }
+        let tip = (x, y + sign * size * 1.5);
+        for side in [-1.0, 1.0].iter() {
+            render_group.push(RenderCommand::Line {
+                x1: x + side * size,
+                y1: y + sign * size * 0.5,
+                x2: tip.0,
+                y2: tip.1,
+                thickness: BORDER_WIDTH,
+                color,
+            });
+        }
+    }
+}
+
+pub fn cell_center(maze: &Maze, cell: usize) -> (f32, f32) {
+    let average = |points: &[(f32, f32)]| {
+        let (x, y) = points
+            .iter()
+            .fold((0.0, 0.0), |(x, y), p| (x + p.0, y + p.1));
+        (x / points.len() as f32, y / points.len() as f32)
+    };
+    match maze.topology() {
+        Topology::Square | Topology::Weave => {
+            let x = maze.level_cell(cell) % maze.width();
+            let y = maze.level_cell(cell) / maze.width();
+            (
+                (x as f32 + 0.5) * TILE_WIDTH,
+                (y as f32 + 0.5) * TILE_HEIGHT,
+            )
+        }
+        Topology::Hex => average(&hex_corners(maze, cell)),
+        Topology::Triangle => average(&triangle_corners(maze, cell)),
+        Topology::Polar => {
+            let sector = PolarSector::new(maze, cell);
+            let radius = (sector.inner + sector.outer) / 2.0;
+            let angle = (sector.start + sector.end) / 2.0;
+            let center = polar_center(maze);
+            (center + radius * angle.cos(), center + radius * angle.sin())
+        }
+    }
+}
+
+fn render_square_borders(render_group: &mut RenderGroup, maze: &Maze, idx: usize, color: Color) {
+    let x = maze.level_cell(idx) % maze.width();
+    let y = maze.level_cell(idx) / maze.width();
     let links = maze.cells()[idx].links();
     let cell_x = x as f32 * TILE_WIDTH;
     let cell_y = y as f32 * TILE_HEIGHT;
-    if !links.north {
+    if !links.has(Neighbor::North) {
         let command = RenderCommand::FillRectangle {
             x: cell_x,
             y: cell_y,
@@ -61,7 +243,7 @@ pub fn render_borders(
         };
         render_group.push(command);
     }
-    if !links.south {
+    if !links.has(Neighbor::South) {
         let command = RenderCommand::FillRectangle {
             x: cell_x,
             y: cell_y + TILE_HEIGHT - BORDER_HEIGHT,
@@ -71,7 +253,7 @@ pub fn render_borders(
         };
         render_group.push(command);
     }
-    if !links.east {
+    if !links.has(Neighbor::East) {
         let command = RenderCommand::FillRectangle {
             x: cell_x + TILE_WIDTH - BORDER_WIDTH,
             y: cell_y,
@@ -81,7 +263,7 @@ pub fn render_borders(
         };
         render_group.push(command);
     }
-    if !links.west {
+    if !links.has(Neighbor::West) {
         let command = RenderCommand::FillRectangle {
             x: cell_x,
             y: cell_y,
@@ -91,4 +273,361 @@ pub fn render_borders(
         };
         render_group.push(command);
     }
+    render_wrap_markers(render_group, maze, idx, (cell_x, cell_y), color);
+}
+
+/// how far passages wrapping around the grid stick out of it
+const WRAP_MARKER: f32 = TILE_WIDTH / 4.0;
+
+/// Passages that wrap around the grid continue a bit past its edge
+fn render_wrap_markers(
+    render_group: &mut RenderGroup,
+    maze: &Maze,
+    cell: usize,
+    (x, y): (f32, f32),
+    color: Color,
+) {
+    let (right, bottom) = (x + TILE_WIDTH, y + TILE_HEIGHT);
+    let markers = [
+        (
+            Neighbor::North,
+            [
+                (x, y, x, y - WRAP_MARKER),
+                (right, y, right, y - WRAP_MARKER),
+            ],
+        ),
+        (
+            Neighbor::South,
+            [
+                (x, bottom, x, bottom + WRAP_MARKER),
+                (right, bottom, right, bottom + WRAP_MARKER),
+            ],
+        ),
+        (
+            Neighbor::West,
+            [
+                (x, y, x - WRAP_MARKER, y),
+                (x, bottom, x - WRAP_MARKER, bottom),
+            ],
+        ),
+        (
+            Neighbor::East,
+            [
+                (right, y, right + WRAP_MARKER, y),
+                (right, bottom, right + WRAP_MARKER, bottom),
+            ],
+        ),
+    ];
+    let links = maze.cells()[cell].links();
+    for (dir, lines) in markers.iter() {
+        if !links.has(*dir) || !maze.is_wrapped(cell, *dir) {
+            continue;
+        }
+        for (x1, y1, x2, y2) in lines.iter().cloned() {
+            render_group.push(RenderCommand::Line {
+                x1,
+                y1,
+                x2,
+                y2,
+                thickness: BORDER_WIDTH,
+                color,
+            });
+        }
+    }
+}
+
+/// gap between the walls of a weave cell and its tile, where passages cross
+const WEAVE_INSET: f32 = TILE_WIDTH * 0.15;
+
+/// top left corner of the tile of a weave cell; under cells share the tile of the cell above
+fn weave_tile(maze: &Maze, cell: usize) -> (f32, f32) {
+    let tile = maze.level_cell(cell);
+    let x = (tile % maze.width()) as f32 * TILE_WIDTH;
+    let y = (tile / maze.width()) as f32 * TILE_HEIGHT;
+    (x, y)
+}
+
+/// under cells only fill the parts of the tunnel that stick out of the cell above
+fn render_weave_cell(render_group: &mut RenderGroup, maze: &Maze, cell: usize, color: Color) {
+    let (x, y) = weave_tile(maze, cell);
+    let narrow = TILE_WIDTH - 2.0 * WEAVE_INSET;
+    let rects = if maze.over_cell(cell).is_none() {
+        vec![(x, y, TILE_WIDTH, TILE_HEIGHT)]
+    } else if maze.cell(cell).has_link(Neighbor::North) {
+        vec![
+            (x + WEAVE_INSET, y, narrow, WEAVE_INSET),
+            (
+                x + WEAVE_INSET,
+                y + TILE_HEIGHT - WEAVE_INSET,
+                narrow,
+                WEAVE_INSET,
+            ),
+        ]
+    } else {
+        vec![
+            (x, y + WEAVE_INSET, WEAVE_INSET, narrow),
+            (
+                x + TILE_WIDTH - WEAVE_INSET,
+                y + WEAVE_INSET,
+                WEAVE_INSET,
+                narrow,
+            ),
+        ]
+    };
+    for (x, y, width, height) in rects {
+        render_group.push(RenderCommand::FillRectangle {
+            x,
+            y,
+            width,
+            height,
+            color,
+        });
+    }
+}
+
+/// Cells are drawn inset in their tile, and passages as corridors from the inset to the tile
+/// edge. A passage crossing over a tunnel then looks like a bridge.
+fn render_weave_borders(render_group: &mut RenderGroup, maze: &Maze, cell: usize, color: Color) {
+    let links = maze.cells()[cell].links();
+    let (x, y) = weave_tile(maze, cell);
+    let (x1, x2, x3, x4) = (
+        x,
+        x + WEAVE_INSET,
+        x + TILE_WIDTH - WEAVE_INSET,
+        x + TILE_WIDTH,
+    );
+    let (y1, y2, y3, y4) = (
+        y,
+        y + WEAVE_INSET,
+        y + TILE_HEIGHT - WEAVE_INSET,
+        y + TILE_HEIGHT,
+    );
+    // for each side: the two walls of a passage, and the wall when there is none
+    let sides = [
+        (
+            Neighbor::North,
+            [(x2, y1, x2, y2), (x3, y1, x3, y2)],
+            (x2, y2, x3, y2),
+        ),
+        (
+            Neighbor::South,
+            [(x2, y3, x2, y4), (x3, y3, x3, y4)],
+            (x2, y3, x3, y3),
+        ),
+        (
+            Neighbor::West,
+            [(x1, y2, x2, y2), (x1, y3, x2, y3)],
+            (x2, y2, x2, y3),
+        ),
+        (
+            Neighbor::East,
+            [(x3, y2, x4, y2), (x3, y3, x4, y3)],
+            (x3, y2, x3, y3),
+        ),
+    ];
+    // the cell above hides the middle of a tunnel
+    let under = maze.over_cell(cell).is_some();
+    let mut line = |(x1, y1, x2, y2)| {
+        render_group.push(RenderCommand::Line {
+            x1,
+            y1,
+            x2,
+            y2,
+            thickness: BORDER_WIDTH,
+            color,
+        })
+    };
+    for (dir, passage, wall) in sides.iter().cloned() {
+        if links.has(dir) {
+            passage.iter().cloned().for_each(&mut line);
+        } else if !under {
+            line(wall);
+        }
+    }
+}
+
+/// distance from the center of an hexagon to any of its corners
+const HEX_SIZE: f32 = TILE_WIDTH / 2.0;
+
+/// corners of a flat topped hexagon, clockwise starting from the east one
+fn hex_corners(maze: &Maze, cell: usize) -> [(f32, f32); 6] {
+    let row = (maze.level_cell(cell) / maze.width()) as f32;
+    let col = maze.level_cell(cell) % maze.width();
+    // half the width of the north/south sides
+    let a = HEX_SIZE / 2.0;
+    // half the height of the hexagon
+    let b = HEX_SIZE * 3.0f32.sqrt() / 2.0;
+    let cx = HEX_SIZE + 3.0 * a * col as f32;
+    let cy = b + row * 2.0 * b + if col % 2 == 0 { 0.0 } else { b };
+    [
+        (cx + HEX_SIZE, cy),
+        (cx + a, cy + b),
+        (cx - a, cy + b),
+        (cx - HEX_SIZE, cy),
+        (cx - a, cy - b),
+        (cx + a, cy - b),
+    ]
+}
+
+fn render_hex_borders(render_group: &mut RenderGroup, maze: &Maze, cell: usize, color: Color) {
+    let links = maze.cells()[cell].links();
+    let [east, south_east, south_west, west, north_west, north_east] = hex_corners(maze, cell);
+    let sides = [
+        (Neighbor::SouthEast, east, south_east),
+        (Neighbor::South, south_east, south_west),
+        (Neighbor::SouthWest, south_west, west),
+        (Neighbor::NorthWest, west, north_west),
+        (Neighbor::North, north_west, north_east),
+        (Neighbor::NorthEast, north_east, east),
+    ];
+    for (dir, (x1, y1), (x2, y2)) in sides.iter().cloned() {
+        if !links.has(dir) {
+            render_group.push(RenderCommand::Line {
+                x1,
+                y1,
+                x2,
+                y2,
+                thickness: BORDER_WIDTH,
+                color,
+            });
+        }
+    }
+}
+
+/// corners of a triangle: the apex first, then the west and east ends of its base
+fn triangle_corners(maze: &Maze, cell: usize) -> [(f32, f32); 3] {
+    let row = (maze.level_cell(cell) / maze.width()) as f32;
+    let col = (maze.level_cell(cell) % maze.width()) as f32;
+    let half_width = TILE_WIDTH / 2.0;
+    let half_height = TILE_WIDTH * 3.0f32.sqrt() / 4.0;
+    let cx = half_width + col * half_width;
+    let cy = half_height + row * 2.0 * half_height;
+    if maze.is_upright(cell) {
+        [
+            (cx, cy - half_height),
+            (cx - half_width, cy + half_height),
+            (cx + half_width, cy + half_height),
+        ]
+    } else {
+        [
+            (cx, cy + half_height),
+            (cx - half_width, cy - half_height),
+            (cx + half_width, cy - half_height),
+        ]
+    }
+}
+
+fn render_triangle_borders(render_group: &mut RenderGroup, maze: &Maze, cell: usize, color: Color) {
+    let links = maze.cells()[cell].links();
+    let [apex, west, east] = triangle_corners(maze, cell);
+    let base = if maze.is_upright(cell) {
+        Neighbor::South
+    } else {
+        Neighbor::North
+    };
+    let sides = [
+        (Neighbor::West, apex, west),
+        (Neighbor::East, apex, east),
+        (base, west, east),
+    ];
+    for (dir, (x1, y1), (x2, y2)) in sides.iter().cloned() {
+        if !links.has(dir) {
+            render_group.push(RenderCommand::Line {
+                x1,
+                y1,
+                x2,
+                y2,
+                thickness: BORDER_WIDTH,
+                color,
+            });
+        }
+    }
+}
+
+const RING_HEIGHT: f32 = TILE_HEIGHT;
+
+/// A cell of a polar maze: the part of a ring between two angles
+struct PolarSector {
+    inner: f32,
+    outer: f32,
+    start: f32,
+    end: f32,
+    /// the next ring splits this cell in two
+    split: bool,
+}
+
+impl PolarSector {
+    fn new(maze: &Maze, cell: usize) -> Self {
+        let grid = maze.polar_grid().expect("not a polar maze");
+        let (ring, pos) = grid.locate(cell);
+        let theta = 2.0 * PI / grid.ring_len(ring) as f32;
+        // the hub in the middle is as wide as a ring
+        let inner = (ring + 1) as f32 * RING_HEIGHT;
+        Self {
+            inner,
+            outer: inner + RING_HEIGHT,
+            start: pos as f32 * theta,
+            end: (pos + 1) as f32 * theta,
+            split: ring + 1 < grid.rings() && grid.split(ring + 1) == 2,
+        }
+    }
+}
+
+/// the whole maze fits in a square whose top left corner is the origin
+fn polar_center(maze: &Maze) -> f32 {
+    (maze.height() + 1) as f32 * RING_HEIGHT
+}
+
+/// points along an arc around the center of a polar maze, roughly one every 10 units
+fn arc_points(center: f32, radius: f32, start: f32, end: f32) -> Vec<(f32, f32)> {
+    let segments = ((end - start) * radius / 10.0).ceil().max(1.0) as usize;
+    (0..=segments)
+        .map(|i| {
+            let angle = start + (end - start) * i as f32 / segments as f32;
+            (center + radius * angle.cos(), center + radius * angle.sin())
+        })
+        .collect()
+}
+
+fn render_polar_borders(render_group: &mut RenderGroup, maze: &Maze, cell: usize, color: Color) {
+    let links = maze.cells()[cell].links();
+    let sector = PolarSector::new(maze, cell);
+    let center = polar_center(maze);
+    let arc = |radius, start, end| RenderCommand::Arc {
+        x: center,
+        y: center,
+        radius,
+        start,
+        end,
+        thickness: BORDER_WIDTH,
+        color,
+    };
+    let line = |angle: f32| RenderCommand::Line {
+        x1: center + sector.inner * angle.cos(),
+        y1: center + sector.inner * angle.sin(),
+        x2: center + sector.outer * angle.cos(),
+        y2: center + sector.outer * angle.sin(),
+        thickness: BORDER_WIDTH,
+        color,
+    };
+    if !links.has(Neighbor::Inward) {
+        render_group.push(arc(sector.inner, sector.start, sector.end));
+    }
+    if !links.has(Neighbor::CounterClockwise) {
+        render_group.push(line(sector.start));
+    }
+    if !links.has(Neighbor::Clockwise) {
+        render_group.push(line(sector.end));
+    }
+    if sector.split {
+        let middle = (sector.start + sector.end) / 2.0;
+        if !links.has(Neighbor::Outward) {
+            render_group.push(arc(sector.outer, sector.start, middle));
+        }
+        if !links.has(Neighbor::OutwardClockwise) {
+            render_group.push(arc(sector.outer, middle, sector.end));
+        }
+    } else if !links.has(Neighbor::Outward) {
+        render_group.push(arc(sector.outer, sector.start, sector.end));
+    }
 }
@@ -0,0 +1,231 @@
+use crate::generator::Generator;
+use crate::maze::Maze;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// first bytes of every save file
+const MAGIC: &[u8; 4] = b"MAZE";
+/// bumped whenever the layout changes
+pub const VERSION: u16 = 1;
+
+/// A generated maze, with what is needed to generate it again.
+///
+/// Files start with a header, every number is little endian:
+///
+/// | bytes | field                                            |
+/// |-------|--------------------------------------------------|
+/// | 4     | magic `MAZE`                                     |
+/// | 2     | version, see `VERSION`                           |
+/// | 1     | generator, in the order of `Generator`           |
+/// | 8     | seed                                             |
+///
+/// and go on with the maze itself, see `Maze::write`
+#[derive(Clone, Eq, PartialEq)]
+pub struct SavedMaze {
+    pub maze: Maze,
+    pub generator: Generator,
+    /// the rng of the generator is seeded with it
+    pub seed: u64,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    /// not a maze file
+    BadMagic,
+    /// written by a newer version
+    UnsupportedVersion(u16),
+    /// the file ends in the middle of a maze
+    Truncated,
+    /// a field holds a value no maze can have
+    Corrupt(&'static str),
+}
+
+impl From<&'static str> for SaveError {
+    fn from(e: &'static str) -> Self {
+        SaveError::Corrupt(e)
+    }
+}
+
+impl From<io::Error> for SaveError {
+    fn from(e: io::Error) -> Self {
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+            SaveError::Truncated
+        } else {
+            SaveError::Io(e)
+        }
+    }
+}
+
+impl SavedMaze {
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write(&mut file)?;
+        file.flush()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SaveError> {
+        Self::read(&mut BufReader::new(File::open(path)?))
+    }
+
+    pub fn write(&self, w: &mut impl Write) -> io::Result<()> {
+        w.write_all(MAGIC)?;
+        w.write_all(&VERSION.to_le_bytes())?;
+        w.write_all(&[generator_id(self.generator)])?;
+        w.write_all(&self.seed.to_le_bytes())?;
+        self.maze.write(w)
+    }
+
+    /// Reads a whole file, bytes after the maze are an error
+    pub fn read(r: &mut impl Read) -> Result<Self, SaveError> {
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(SaveError::BadMagic);
+        }
+        let version = read_u16(r)?;
+        if version != VERSION {
+            return Err(SaveError::UnsupportedVersion(version));
+        }
+        let generator = generator_from_id(read_u8(r)?)?;
+        let seed = read_u64(r)?;
+        let maze = Maze::read(r)?;
+        if r.read(&mut [0])? != 0 {
+            return Err(SaveError::Corrupt("bytes after the maze"));
+        }
+        Ok(Self {
+            maze,
+            generator,
+            seed,
+        })
+    }
+}
+
+fn generator_id(generator: Generator) -> u8 {
+    match generator {
+        Generator::BinaryTree => 0,
+        Generator::Sidewind => 1,
+        Generator::Wilson => 2,
+        Generator::HuntAndKill => 3,
+        Generator::RecurBacktracker => 4,
+        Generator::AldousBroder => 5,
+        Generator::Kruskal => 6,
+        Generator::Prim => 7,
+        Generator::TruePrim => 8,
+    }
+}
+
+fn generator_from_id(id: u8) -> Result<Generator, SaveError> {
+    match id {
+        0 => Ok(Generator::BinaryTree),
+        1 => Ok(Generator::Sidewind),
+        2 => Ok(Generator::Wilson),
+        3 => Ok(Generator::HuntAndKill),
+        4 => Ok(Generator::RecurBacktracker),
+        5 => Ok(Generator::AldousBroder),
+        6 => Ok(Generator::Kruskal),
+        7 => Ok(Generator::Prim),
+        8 => Ok(Generator::TruePrim),
+        _ => Err(SaveError::Corrupt("unknown generator")),
+    }
+}
+
+pub fn read_u8(r: &mut impl Read) -> Result<u8, SaveError> {
+    let mut bytes = [0; 1];
+    r.read_exact(&mut bytes)?;
+    Ok(bytes[0])
+}
+
+pub fn read_u16(r: &mut impl Read) -> Result<u16, SaveError> {
+    let mut bytes = [0; 2];
+    r.read_exact(&mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
+pub fn read_u32(r: &mut impl Read) -> Result<u32, SaveError> {
+    let mut bytes = [0; 4];
+    r.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+pub fn read_u64(r: &mut impl Read) -> Result<u64, SaveError> {
+    let mut bytes = [0; 8];
+    r.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{HuntAndKillGen, MazeGenerator};
+    use crate::maze::{Mask, Topology, Wrap};
+    use rand::{prelude::StdRng, SeedableRng};
+
+    fn saved(maze: Maze) -> SavedMaze {
+        let seed = 42;
+        let mut generator = HuntAndKillGen::new(maze);
+        generator.finish(&mut StdRng::seed_from_u64(seed));
+        SavedMaze {
+            maze: generator.maze().clone(),
+            generator: Generator::HuntAndKill,
+            seed,
+        }
+    }
+
+    fn bytes(saved: &SavedMaze) -> Vec<u8> {
+        let mut bytes = vec![];
+        saved.write(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut mask = Mask::new(6, 5);
+        mask.disable(0);
+        let mazes = vec![
+            Maze::with_mask(mask),
+            Maze::new(5, 4).wrapped(Wrap::Torus),
+            Maze::with_levels(Topology::Hex, Mask::new(4, 4), 3),
+            Maze::with_topology(Topology::Weave, Mask::new(8, 8)),
+            Maze::polar(4),
+        ];
+        for maze in mazes {
+            let saved = saved(maze);
+            let read = SavedMaze::read(&mut bytes(&saved).as_slice());
+            assert!(read.ok() == Some(saved));
+        }
+    }
+
+    #[test]
+    fn corrupt() {
+        let bytes = bytes(&saved(Maze::new(4, 3)));
+        let read = |mut bytes: &[u8]| SavedMaze::read(&mut bytes).err().unwrap();
+        for len in 0..bytes.len() {
+            assert!(matches!(read(&bytes[..len]), SaveError::Truncated));
+        }
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(read(&trailing), SaveError::Corrupt(_)));
+        let mut magic = bytes.clone();
+        magic[0] = b'X';
+        assert!(matches!(read(&magic), SaveError::BadMagic));
+        let mut version = bytes.clone();
+        version[4] = 2;
+        assert!(matches!(read(&version), SaveError::UnsupportedVersion(2)));
+        // the links of the last cell point east, off the grid
+        let mut links = bytes.clone();
+        let last = links.len() - 1;
+        links[last] |= 1 << 2;
+        assert!(matches!(read(&links), SaveError::Corrupt("broken links")));
+        // up on a single level, then south east on a square grid
+        for bit in [6, 4].iter() {
+            let mut links = bytes.clone();
+            links[last] |= 1 << bit;
+            assert!(matches!(
+                read(&links),
+                SaveError::Corrupt("link in a direction the grid does not have")
+            ));
+        }
+    }
+}
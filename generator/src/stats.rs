@@ -0,0 +1,178 @@
+use crate::grid::Grid;
+use crate::maze::MAX_NEIGHBORS;
+use std::collections::VecDeque;
+
+/// Texture of a maze, to compare what generators produce
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stats {
+    /// enabled cells, without the tunnels under other ones
+    pub cells: usize,
+    /// cells by number of links, junctions are the cells with 3 or more
+    pub degrees: [usize; MAX_NEIGHBORS + 1],
+    /// cells with two links on opposite sides
+    pub straights: usize,
+    /// runs of passages from a dead end or junction to the next one
+    pub corridors: usize,
+    /// links between two cells
+    pub passages: usize,
+    /// steps along the longest path, see `dijkstra::longest_path`
+    pub solution_length: usize,
+    /// steps along the longest path, for each step between its ends without walls. Positions
+    /// mean nothing on hex and polar grids, the steps are counted on the grid instead
+    pub tortuosity: f64,
+    /// see `Maze::canonical_hash`, only mazes have one
+    pub hash: Option<u64>,
+}
+
+impl Stats {
+    /// `longest_path` is the one of `dijkstra::longest_path`
+    pub fn new<G: Grid>(maze: &G, longest_path: &[usize]) -> Self {
+        let mut degrees = [0; MAX_NEIGHBORS + 1];
+        let mut straights = 0;
+        let mut cells = 0;
+        for cell in (0..maze.len()).filter(|c| maze.is_enabled(*c)) {
+            let links = maze.linked_neighbors(cell);
+            let mut dirs = links.iter().map(|n| n.dir);
+            let degree = links.iter().count();
+            degrees[degree] += 1;
+            if !maze.is_under(cell) {
+                cells += 1;
+            }
+            if let (2, Some(a), Some(b)) = (degree, dirs.next(), dirs.next()) {
+                if a.opposite() == b || b.opposite() == a {
+                    straights += 1;
+                }
+            }
+        }
+        let links: usize = degrees.iter().enumerate().map(|(d, c)| d * c).sum();
+        // every corridor ends at two cells that are not corridors themselves
+        let ends = links - 2 * degrees[2];
+        let solution_length = longest_path.len().saturating_sub(1);
+        let tortuosity = match (longest_path.first(), longest_path.last()) {
+            (Some(from), Some(to)) => {
+                let distance = open_distance(maze, *from, *to);
+                solution_length as f64 / distance.max(1) as f64
+            }
+            _ => 0.0,
+        };
+        Self {
+            cells,
+            degrees,
+            straights,
+            corridors: ends / 2,
+            passages: links / 2,
+            solution_length,
+            tortuosity,
+            hash: None,
+        }
+    }
+
+    pub fn dead_ends(&self) -> usize {
+        self.degrees[1]
+    }
+
+    pub fn dead_end_ratio(&self) -> f64 {
+        ratio(self.dead_ends(), self.cells)
+    }
+
+    /// junctions by number of links, from 3 links up
+    pub fn junctions(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.degrees
+            .iter()
+            .cloned()
+            .enumerate()
+            .skip(3)
+            .filter(|(_, count)| *count > 0)
+    }
+
+    /// share of the cells with two links that go straight
+    pub fn river_factor(&self) -> f64 {
+        ratio(self.straights, self.degrees[2])
+    }
+
+    /// steps between dead ends and junctions
+    pub fn average_corridor_length(&self) -> f64 {
+        ratio(self.passages, self.corridors)
+    }
+}
+
+/// steps from `from` to `to` if every wall was removed
+fn open_distance<G: Grid>(maze: &G, from: usize, to: usize) -> usize {
+    let mut distances = vec![None; maze.len()];
+    distances[from] = Some(0);
+    let mut pending = VecDeque::new();
+    pending.push_back(from);
+    while let Some(cell) = pending.pop_front() {
+        let distance = distances[cell].unwrap_or(0);
+        if cell == to {
+            return distance;
+        }
+        for n in maze.neighbors(cell).iter() {
+            if distances[n.idx].is_none() {
+                distances[n.idx] = Some(distance + 1);
+                pending.push_back(n.idx);
+            }
+        }
+    }
+    0
+}
+
+fn ratio(a: usize, b: usize) -> f64 {
+    if b == 0 {
+        0.0
+    } else {
+        a as f64 / b as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra;
+    use crate::generator::{MazeGenerator, RecurBacktrackerGen};
+    use crate::maze::{Mask, Maze, Neighbor, Topology};
+    use rand::{prelude::StdRng, SeedableRng};
+
+    #[test]
+    fn stats() {
+        // 0 1 2    the middle column goes down from 1 to 7, 3 tunnels under 4 to 5
+        // 3 4 5
+        // 6 7 8
+        let mut maze = Maze::with_topology(Topology::Weave, Mask::new(3, 3));
+        maze.link(0, Neighbor::East);
+        maze.link(0, Neighbor::South);
+        maze.link(1, Neighbor::South);
+        maze.link(4, Neighbor::South);
+        maze.link(3, Neighbor::South);
+        maze.link(2, Neighbor::South);
+        maze.link(5, Neighbor::South);
+        maze.tunnel(3, Neighbor::East);
+        assert!(maze.validate().is_perfect());
+
+        let path = dijkstra::longest_path(&maze);
+        let stats = Stats::new(&maze, &path);
+        assert_eq!(stats.cells, 9);
+        assert_eq!(stats.degrees[..4], [0, 4, 4, 2]);
+        assert_eq!(stats.dead_ends(), 4);
+        assert_eq!(stats.junctions().collect::<Vec<_>>(), vec![(3, 2)]);
+        // 4 down the middle and the tunnel
+        assert_eq!(stats.straights, 2);
+        assert_eq!(stats.passages, 9);
+        assert_eq!(stats.corridors, 5);
+        // from 7 up to 0 and through the tunnel to 8, right next to it
+        assert_eq!(stats.solution_length, 7);
+        assert!((stats.tortuosity - 7.0).abs() < 1e-9);
+        assert!((stats.river_factor() - 0.5).abs() < 1e-9);
+        assert!((stats.average_corridor_length() - 1.8).abs() < 1e-9);
+        assert!((stats.dead_end_ratio() - 4.0 / 9.0).abs() < 1e-9);
+
+        // no path is shorter than the one without walls, whatever the grid
+        let hex = Maze::with_topology(Topology::Hex, Mask::new(5, 4));
+        for maze in vec![Maze::polar(4), hex] {
+            let mut generator = RecurBacktrackerGen::new(maze);
+            generator.finish(&mut StdRng::seed_from_u64(2));
+            let path = dijkstra::longest_path(generator.maze());
+            assert!(Stats::new(generator.maze(), &path).tortuosity >= 1.0);
+        }
+    }
+}
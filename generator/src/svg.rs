@@ -0,0 +1,495 @@
+use crate::grid::Grid;
+use crate::maze::{Maze, Neighbor, Topology};
+use crate::render::{self, TILE_HEIGHT, TILE_WIDTH};
+use host_api::{Color, RenderCommand, RenderGroup};
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::fmt::Write;
+
+/// Sizes are in pixels of the svg, colors are written as `#rrggbb`
+#[derive(Clone, Debug)]
+pub struct SvgStyle {
+    pub cell_size: f32,
+    pub wall_width: f32,
+    pub wall_color: Color,
+    pub background: Color,
+    /// heatmap colors of the start, and of the cells the furthest from it
+    pub near_color: Color,
+    pub far_color: Color,
+    pub path_width: f32,
+    pub path_color: Color,
+    pub start_color: Color,
+    pub goal_color: Color,
+}
+
+impl Default for SvgStyle {
+    fn default() -> Self {
+        let rgb = |r, g, b| Color { r, g, b };
+        Self {
+            cell_size: 20.0,
+            wall_width: 2.0,
+            wall_color: rgb(0, 0, 0),
+            background: rgb(255, 255, 255),
+            near_color: rgb(255, 255, 255),
+            far_color: rgb(30, 60, 150),
+            path_width: 4.0,
+            path_color: render::RED,
+            start_color: rgb(40, 160, 60),
+            goal_color: render::DARK_RED,
+        }
+    }
+}
+
+/// Vector drawing of a single level of a maze, with optional layers on top of it
+pub struct Svg<'a> {
+    maze: &'a Maze,
+    level: usize,
+    style: SvgStyle,
+    distances: Option<&'a [usize]>,
+    path: Option<&'a [usize]>,
+    markers: Option<(usize, usize)>,
+}
+
+impl<'a> Svg<'a> {
+    pub fn new(maze: &'a Maze) -> Self {
+        Self {
+            maze,
+            level: 0,
+            style: SvgStyle::default(),
+            distances: None,
+            path: None,
+            markers: None,
+        }
+    }
+
+    pub fn level(mut self, level: usize) -> Self {
+        self.level = level;
+        self
+    }
+
+    pub fn style(mut self, style: SvgStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// heatmap of the distances, see `dijkstra::flood`
+    pub fn distances(mut self, distances: &'a [usize]) -> Self {
+        self.distances = Some(distances);
+        self
+    }
+
+    /// a line through the centers of the cells, like `dijkstra::longest_path`
+    pub fn path(mut self, path: &'a [usize]) -> Self {
+        self.path = Some(path);
+        self
+    }
+
+    pub fn markers(mut self, start: usize, goal: usize) -> Self {
+        self.markers = Some((start, goal));
+        self
+    }
+
+    pub fn write(&self) -> String {
+        let walls = self.walls();
+        // maze coordinates are those of the renderer, the view box scales them down
+        let scale = self.style.cell_size / TILE_WIDTH;
+        let pad = self.style.wall_width.max(self.style.path_width) / scale;
+        let (min, max) = walls.bounds();
+        let (x, y) = (min.0 - pad, min.1 - pad);
+        let (width, height) = (max.0 - min.0 + 2.0 * pad, max.1 - min.1 + 2.0 * pad);
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="{} {} {} {}">"#,
+            num(width * scale),
+            num(height * scale),
+            num(x),
+            num(y),
+            num(width),
+            num(height)
+        );
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+            num(x),
+            num(y),
+            num(width),
+            num(height),
+            hex(self.style.background)
+        );
+        if let Some(distances) = self.distances {
+            self.write_heatmap(&mut svg, distances);
+        }
+        let _ = writeln!(
+            svg,
+            r#"<path d="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linecap="round"/>"#,
+            walls.path_data(),
+            hex(self.style.wall_color),
+            num(self.style.wall_width / scale)
+        );
+        if let Some(path) = self.path {
+            self.write_path(&mut svg, path, scale);
+        }
+        if let Some((start, goal)) = self.markers {
+            for (cell, color) in [
+                (start, self.style.start_color),
+                (goal, self.style.goal_color),
+            ]
+            .iter()
+            .cloned()
+            {
+                if self.maze.level(cell) != self.level {
+                    continue;
+                }
+                let (cx, cy) = render::cell_center(self.maze, cell);
+                let _ = writeln!(
+                    svg,
+                    r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+                    num(cx),
+                    num(cy),
+                    num(TILE_WIDTH / 4.0),
+                    hex(color)
+                );
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Walls of every cell, square cells are drawn as rectangles by the renderer so their sides
+    /// are taken from the grid instead
+    fn walls(&self) -> Walls {
+        let maze = self.maze;
+        let mut walls = Walls::default();
+        let mut group = RenderGroup::default();
+        for cell in 0..maze.len() {
+            render::render_borders(&mut group, maze, self.level, cell, self.style.wall_color);
+            let square = maze.topology() == Topology::Square;
+            if square && maze.is_enabled(cell) && maze.level(cell) == self.level {
+                let x = (maze.level_cell(cell) % maze.width()) as f32 * TILE_WIDTH;
+                let y = (maze.level_cell(cell) / maze.width()) as f32 * TILE_HEIGHT;
+                let (right, bottom) = (x + TILE_WIDTH, y + TILE_HEIGHT);
+                let sides = [
+                    (Neighbor::North, (x, y), (right, y)),
+                    (Neighbor::South, (x, bottom), (right, bottom)),
+                    (Neighbor::East, (right, y), (right, bottom)),
+                    (Neighbor::West, (x, y), (x, bottom)),
+                ];
+                for (dir, from, to) in sides.iter().cloned() {
+                    if !maze.links(cell).has(dir) {
+                        walls.line(from, to);
+                    }
+                }
+            }
+        }
+        for command in group.drain() {
+            match command {
+                RenderCommand::Line { x1, y1, x2, y2, .. } => walls.line((x1, y1), (x2, y2)),
+                RenderCommand::Arc {
+                    x,
+                    y,
+                    radius,
+                    start,
+                    end,
+                    ..
+                } => walls.arc((x, y), radius, start, end),
+                _ => {}
+            }
+        }
+        walls
+    }
+
+    fn write_heatmap(&self, svg: &mut String, distances: &[usize]) {
+        let maze = self.maze;
+        let max = distances
+            .iter()
+            .filter(|d| **d < maze.len())
+            .max()
+            .cloned()
+            .unwrap_or(0)
+            .max(1);
+        let mut group = RenderGroup::default();
+        for (cell, distance) in distances.iter().enumerate() {
+            if *distance < maze.len() {
+                let t = *distance as f32 / max as f32;
+                let color = mix(self.style.near_color, self.style.far_color, t);
+                render::render_cell(&mut group, maze, self.level, cell, color);
+            }
+        }
+        let _ = writeln!(svg, "<g>");
+        for command in group.drain() {
+            match command {
+                RenderCommand::FillRectangle {
+                    x,
+                    y,
+                    width,
+                    height,
+                    color,
+                } => {
+                    let _ = writeln!(
+                        svg,
+                        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                        num(x),
+                        num(y),
+                        num(width),
+                        num(height),
+                        hex(color)
+                    );
+                }
+                RenderCommand::FillPolygon { points, color } => {
+                    let points: Vec<_> = points
+                        .iter()
+                        .map(|(x, y)| format!("{},{}", num(*x), num(*y)))
+                        .collect();
+                    let _ = writeln!(
+                        svg,
+                        r#"<polygon points="{}" fill="{}"/>"#,
+                        points.join(" "),
+                        hex(color)
+                    );
+                }
+                _ => {}
+            }
+        }
+        let _ = writeln!(svg, "</g>");
+    }
+
+    /// The path is cut where it leaves the level, or wraps around the grid
+    fn write_path(&self, svg: &mut String, path: &[usize], scale: f32) {
+        let maze = self.maze;
+        let mut data = String::new();
+        let mut previous: Option<usize> = None;
+        for cell in path.iter().cloned() {
+            if maze.level(cell) != self.level {
+                previous = None;
+                continue;
+            }
+            let wraps = previous
+                .and_then(|p| maze.from_a_to_b(p, cell).map(|dir| maze.is_wrapped(p, dir)))
+                .unwrap_or(true);
+            let (x, y) = render::cell_center(maze, cell);
+            let command = if wraps { 'M' } else { 'L' };
+            let _ = write!(data, "{}{} {}", command, num(x), num(y));
+            previous = Some(cell);
+        }
+        let _ = writeln!(
+            svg,
+            r#"<path d="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round"/>"#,
+            data,
+            hex(self.style.path_color),
+            num(self.style.path_width / scale)
+        );
+    }
+}
+
+/// Wall segments, merged with the ones on the same line that they touch or overlap. Neighbor
+/// cells both draw the wall between them, and straight walls are made of a segment per cell
+#[derive(Default)]
+struct Walls {
+    /// along lines, keyed by their (rounded) angle and distance to the origin
+    lines: HashMap<(i32, i32), Line>,
+    /// keyed by their (rounded) center and radius
+    arcs: HashMap<(i32, i32, i32), Arc>,
+}
+
+struct Line {
+    angle: f32,
+    offset: f32,
+    /// between positions along the line
+    spans: Vec<(f32, f32)>,
+}
+
+struct Arc {
+    center: (f32, f32),
+    radius: f32,
+    /// between angles, clockwise
+    spans: Vec<(f32, f32)>,
+}
+
+/// coordinates closer than this are the same
+const EPSILON: f32 = 0.01;
+
+fn round(x: f32) -> i32 {
+    (x / EPSILON / 10.0).round() as i32
+}
+
+impl Walls {
+    fn line(&mut self, from: (f32, f32), to: (f32, f32)) {
+        let mut angle = (to.1 - from.1).atan2(to.0 - from.0);
+        if angle < 0.0 {
+            angle += PI;
+        }
+        if angle >= PI - EPSILON {
+            angle = 0.0;
+        }
+        let (dx, dy) = (angle.cos(), angle.sin());
+        // distance of the line to the origin, and positions along it
+        let offset = from.0 * dy - from.1 * dx;
+        let (a, b) = (from.0 * dx + from.1 * dy, to.0 * dx + to.1 * dy);
+        let line = self
+            .lines
+            .entry((round(angle * 100.0), round(offset)))
+            .or_insert(Line {
+                angle,
+                offset,
+                spans: vec![],
+            });
+        line.spans.push((a.min(b), a.max(b)));
+    }
+
+    fn arc(&mut self, center: (f32, f32), radius: f32, start: f32, end: f32) {
+        let key = (round(center.0), round(center.1), round(radius));
+        let arc = self.arcs.entry(key).or_insert(Arc {
+            center,
+            radius,
+            spans: vec![],
+        });
+        arc.spans.push((start, end));
+    }
+
+    /// top left and bottom right corners around every wall
+    fn bounds(&self) -> ((f32, f32), (f32, f32)) {
+        let mut min = (f32::MAX, f32::MAX);
+        let mut max = (f32::MIN, f32::MIN);
+        let mut add = |(x, y): (f32, f32)| {
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        };
+        for line in self.lines.values() {
+            for span in &line.spans {
+                add(line.point(span.0));
+                add(line.point(span.1));
+            }
+        }
+        for arc in self.arcs.values() {
+            add((arc.center.0 - arc.radius, arc.center.1 - arc.radius));
+            add((arc.center.0 + arc.radius, arc.center.1 + arc.radius));
+        }
+        if min.0 > max.0 {
+            ((0.0, 0.0), (0.0, 0.0))
+        } else {
+            (min, max)
+        }
+    }
+
+    /// A single svg path for every wall. Lines and arcs are sorted, so the same maze always
+    /// gives the same drawing
+    fn path_data(&self) -> String {
+        let mut data = String::new();
+        let mut lines: Vec<_> = self.lines.iter().collect();
+        lines.sort_by_key(|(key, _)| **key);
+        for (_, line) in lines {
+            for (a, b) in merge(&line.spans) {
+                let (x1, y1) = line.point(a);
+                let (x2, y2) = line.point(b);
+                let _ = write!(data, "M{} {}L{} {}", num(x1), num(y1), num(x2), num(y2));
+            }
+        }
+        let mut arcs: Vec<_> = self.arcs.iter().collect();
+        arcs.sort_by_key(|(key, _)| **key);
+        for (_, arc) in arcs {
+            for (start, end) in merge(&arc.spans) {
+                // svg arcs cannot draw a whole circle, so they are drawn in halves at most
+                let pieces = ((end - start) / PI).ceil().max(1.0) as usize;
+                let step = (end - start) / pieces as f32;
+                let (x, y) = arc.point(start);
+                let _ = write!(data, "M{} {}", num(x), num(y));
+                for piece in 1..=pieces {
+                    let (x, y) = arc.point(start + step * piece as f32);
+                    let r = num(arc.radius);
+                    let _ = write!(data, "A{} {} 0 0 1 {} {}", r, r, num(x), num(y));
+                }
+            }
+        }
+        data
+    }
+}
+
+impl Line {
+    fn point(&self, along: f32) -> (f32, f32) {
+        let (dx, dy) = (self.angle.cos(), self.angle.sin());
+        (along * dx + self.offset * dy, along * dy - self.offset * dx)
+    }
+}
+
+impl Arc {
+    fn point(&self, angle: f32) -> (f32, f32) {
+        (
+            self.center.0 + self.radius * angle.cos(),
+            self.center.1 + self.radius * angle.sin(),
+        )
+    }
+}
+
+/// joins the spans that overlap or touch
+fn merge(spans: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    let mut spans = spans.to_vec();
+    spans.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let mut merged: Vec<(f32, f32)> = vec![];
+    for (start, end) in spans {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 + EPSILON => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+fn mix(from: Color, to: Color, t: f32) -> Color {
+    let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Color {
+        r: channel(from.r, to.r),
+        g: channel(from.g, to.g),
+        b: channel(from.b, to.b),
+    }
+}
+
+fn hex(color: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+/// short numbers, without trailing zeros
+fn num(x: f32) -> String {
+    let s = format!("{:.2}", x);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" {
+        "0".to_string()
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dijkstra;
+
+    #[test]
+    fn merged_walls() {
+        let mut maze = Maze::new(2, 1);
+        maze.link(0, Neighbor::East);
+        let svg = Svg::new(&maze).write();
+        // one segment per side of the maze, the walls of both cells are joined
+        assert!(svg.contains(r#"d="M0 100L200 100M0 0L200 0M0 0L0 100M200 0L200 100""#));
+
+        let mut maze = Maze::new(2, 2);
+        maze.link(0, Neighbor::East);
+        maze.link(0, Neighbor::South);
+        maze.link(1, Neighbor::South);
+        let path = dijkstra::longest_path(&maze);
+        let distances = dijkstra::flood(0, &maze);
+        let svg = Svg::new(&maze)
+            .distances(&distances)
+            .path(&path)
+            .markers(path[0], path[path.len() - 1])
+            .write();
+        assert_eq!(svg.matches("<rect").count(), 1 + 4);
+        assert_eq!(svg.matches("<circle").count(), 2);
+        assert!(svg.contains(r#"<path d="M50 150L50 50L150 50L150 150""#));
+
+        // the arcs of every cell join into the inner and outer circles, drawn in two halves
+        let svg = Svg::new(&Maze::polar(1)).write();
+        assert_eq!(svg.matches('A').count(), 4);
+    }
+}
@@ -42,6 +42,29 @@ pub enum RenderCommand {
         height: f32,
         color: Color,
     },
+    Line {
+        x1: f32,
+        y1: f32,
+        x2: f32,
+        y2: f32,
+        thickness: f32,
+        color: Color,
+    },
+    /// clockwise arc around `(x, y)`, angles in radians
+    Arc {
+        x: f32,
+        y: f32,
+        radius: f32,
+        start: f32,
+        end: f32,
+        thickness: f32,
+        color: Color,
+    },
+    /// convex (or at least star shaped around its centroid) polygon
+    FillPolygon {
+        points: Vec<(f32, f32)>,
+        color: Color,
+    },
     Text {
         x: f32,
         y: f32,